@@ -5,7 +5,13 @@
 use {
     crate::once::Once,
     alloc::fmt,
-    core::{cell::Cell, ops::Deref},
+    core::{
+        cell::Cell,
+        hash::{Hash, Hasher},
+        mem::MaybeUninit,
+        ops::{Deref, DerefMut},
+        pin::Pin,
+    },
 };
 
 /// A value which is initialized on the first access.
@@ -18,6 +24,7 @@ use {
 /// use std::collections::HashMap;
 /// use nospin::Lazy;
 ///
+/// # #[cfg(not(feature = "strict_send_sync"))]
 /// static HASHMAP: Lazy<HashMap<i32, String>> = Lazy::new(|| {
 ///     println!("initializing");
 ///     let mut m = HashMap::new();
@@ -26,6 +33,7 @@ use {
 ///     m
 /// });
 ///
+/// # #[cfg(not(feature = "strict_send_sync"))]
 /// fn main() {
 ///     println!("ready");
 ///     println!("{:?}", HASHMAP.get(&13));
@@ -37,6 +45,8 @@ use {
 ///     //   Some("Spica")
 ///     //   Some("Hoyten")
 /// }
+/// # #[cfg(feature = "strict_send_sync")]
+/// # fn main() {}
 /// ```
 pub struct Lazy<T, F = fn() -> T> {
     cell: Once<T>,
@@ -55,6 +65,93 @@ impl<T: fmt::Debug, F> fmt::Debug for Lazy<T, F> {
     }
 }
 
+/// Compares the values of two [`Lazy`]s without forcing either.
+///
+/// As with [`Once`], a value that has not been initialized yet counts as a distinct state, equal to other
+/// uninitialized values regardless of their initializing functions.
+///
+/// ```
+/// use nospin::Lazy;
+///
+/// let a: Lazy<u32> = Lazy::new(|| 1);
+/// let b: Lazy<u32> = Lazy::new(|| 2);
+/// assert!(a == b);
+///
+/// Lazy::force(&a);
+/// assert!(a != b);
+/// Lazy::force(&b);
+/// assert!(a != b);
+/// ```
+impl<T: PartialEq, F> PartialEq for Lazy<T, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cell == other.cell
+    }
+}
+
+impl<T: Eq, F> Eq for Lazy<T, F> {}
+
+/// Hashes the value if it has been initialized, without forcing it, consistently with its `PartialEq`
+/// implementation.
+impl<T: Hash, F> Hash for Lazy<T, F> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cell.hash(state)
+    }
+}
+
+impl<T: Clone, F: Clone> Clone for Lazy<T, F> {
+    /// Clones the lazy value, along with its initializing function if it is still present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::Lazy;
+    ///
+    /// let lazy = Lazy::new(|| vec![1, 2]);
+    ///
+    /// let uninit = lazy.clone();
+    /// assert!(!uninit.is_initialized());
+    ///
+    /// Lazy::force(&lazy);
+    /// let init = lazy.clone();
+    /// assert_eq!(init.try_as_ref(), Some(&vec![1, 2]));
+    /// assert_eq!(*uninit, [1, 2]);
+    /// ```
+    fn clone(&self) -> Self {
+        struct Restore<'a, F>(&'a Cell<Option<F>>, Option<F>);
+
+        impl<F> Drop for Restore<'_, F> {
+            fn drop(&mut self) {
+                // Puts the function back even if cloning it panicked, so `self` is not left without one
+                self.0.set(self.1.take());
+            }
+        }
+
+        let init = Restore(&self.init, self.init.take());
+        let cloned = init.1.clone();
+        drop(init);
+
+        Lazy {
+            cell: match self.cell.get() {
+                Some(value) => Once::initialized(value.clone()),
+                None => Once::new(),
+            },
+            init: Cell::new(cloned),
+        }
+    }
+}
+
+/// The error returned by [`Lazy::try_force_ref`] when the initializing function is no longer
+/// available, usually because it previously panicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LazyPoisoned;
+
+impl fmt::Display for LazyPoisoned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Lazy instance has previously been poisoned")
+    }
+}
+
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T, F: Send> Sync for Lazy<T, F> where Once<T>: Sync {}
 
 impl<T, F> Lazy<T, F> {
@@ -68,6 +165,32 @@ impl<T, F> Lazy<T, F> {
         }
     }
 
+    /// Creates a new lazy value backed by an existing [`Once`], with the given initializing function.
+    ///
+    /// If `once` has already been initialized then its value is used as is and `f` is never called. This
+    /// allows a [`Once`] to be initialized eagerly on some code paths while still exposing a [`Lazy`]
+    /// interface elsewhere.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::{Lazy, Once};
+    ///
+    /// let eager = Lazy::from_once(Once::initialized(1), || unreachable!());
+    /// assert_eq!(*eager, 1);
+    ///
+    /// let lazy = Lazy::from_once(Once::new(), || 2);
+    /// assert!(!lazy.is_initialized());
+    /// assert_eq!(*lazy, 2);
+    /// ```
+    #[inline(always)]
+    pub const fn from_once(once: Once<T>, f: F) -> Lazy<T, F> {
+        Lazy {
+            cell: once,
+            init: Cell::new(Some(f)),
+        }
+    }
+
     /// Retrieves a mutable pointer to the inner data.
     ///
     /// This is especially useful when interfacing with low level code or FFI where the caller
@@ -76,9 +199,94 @@ impl<T, F> Lazy<T, F> {
     pub fn as_mut_ptr(&self) -> *mut T {
         self.cell.as_mut_ptr()
     }
+
+    /// Returns `true` if the lazy value has been initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::Lazy;
+    ///
+    /// let lazy = Lazy::new(|| 92);
+    ///
+    /// assert!(!lazy.is_initialized());
+    /// Lazy::force(&lazy);
+    /// assert!(lazy.is_initialized());
+    /// ```
+    pub fn is_initialized(&self) -> bool {
+        self.cell.is_completed()
+    }
+
+    /// Returns `true` if the initializing function is still present, i.e. it has not been
+    /// consumed by [`Lazy::force`].
+    ///
+    /// Together with [`Lazy::is_initialized`] this distinguishes an uninitialized value (not
+    /// initialized, initializer present) from one poisoned by a panicking [`Lazy::force`] (not
+    /// initialized, initializer consumed) without forcing it. [`Lazy::force_with`] never consumes
+    /// the initializer, so a value poisoned by a panicking `force_with` still reports it as present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::Lazy;
+    ///
+    /// let lazy = Lazy::new(|| -> u32 { panic!() });
+    /// assert!(lazy.poll_initializer());
+    ///
+    /// let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| Lazy::force(&lazy)));
+    ///
+    /// // Poisoned
+    /// assert!(!lazy.poll_initializer());
+    /// assert!(!lazy.is_initialized());
+    /// ```
+    pub fn poll_initializer(&self) -> bool {
+        let init = self.init.take();
+        let present = init.is_some();
+        self.init.set(init);
+        present
+    }
+
+    /// Returns a reference to the value if it has already been initialized, without forcing it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::Lazy;
+    ///
+    /// let lazy = Lazy::new(|| 92);
+    ///
+    /// assert_eq!(lazy.try_as_ref(), None);
+    /// Lazy::force(&lazy);
+    /// assert_eq!(lazy.try_as_ref(), Some(&92));
+    /// ```
+    pub fn try_as_ref(&self) -> Option<&T> {
+        self.cell.get()
+    }
+
+    /// Returns a reference to the value if it has already been initialized. This never runs the
+    /// initializing function, nor has any other side effect.
+    ///
+    /// This is the same as [`Lazy::try_as_ref`], named to make plain that the value is not forced. It is an
+    /// associated function that needs to be used as `Lazy::peek(...)`, so as not to conflict with a method of the
+    /// same name on the value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::Lazy;
+    ///
+    /// let lazy = Lazy::new(|| 92);
+    ///
+    /// assert_eq!(Lazy::peek(&lazy), None);
+    /// assert_eq!(*lazy, 92);
+    /// assert_eq!(Lazy::peek(&lazy), Some(&92));
+    /// ```
+    pub fn peek(this: &Self) -> Option<&T> {
+        this.cell.get()
+    }
 }
 
-impl<T, F: FnOnce() -> T> Lazy<T, F> {
+impl<T, F: LazyInit<T>> Lazy<T, F> {
     /// Forces the evaluation of this lazy value and
     /// returns a reference to result. This is equivalent
     /// to the `Deref` impl, but is explicit.
@@ -94,14 +302,159 @@ impl<T, F: FnOnce() -> T> Lazy<T, F> {
     /// assert_eq!(&*lazy, &92);
     /// ```
     pub fn force(this: &Self) -> &T {
-        this.cell.call_once(|| match this.init.take() {
-            Some(f) => f(),
-            None => panic!("Lazy instance has previously been poisoned"),
-        })
+        match Self::try_force_ref(this) {
+            Ok(value) => value,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Forces the evaluation of this lazy value and returns a reference to the result, or
+    /// [`LazyPoisoned`] rather than panicking if the value can no longer be initialized.
+    ///
+    /// This happens when a previous initialization attempt panicked, including one made with
+    /// [`Lazy::force_with`], or when this is called re-entrantly from within the initializing function
+    /// itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::{Lazy, lazy::LazyPoisoned};
+    ///
+    /// let lazy = Lazy::new(|| 92);
+    /// assert_eq!(Lazy::try_force_ref(&lazy), Ok(&92));
+    ///
+    /// let poisoned = Lazy::new(|| -> u32 { panic!() });
+    /// let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| Lazy::force(&poisoned)));
+    /// assert_eq!(Lazy::try_force_ref(&poisoned), Err(LazyPoisoned));
+    ///
+    /// let poisoned = Lazy::new(|| 92);
+    /// let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     Lazy::force_with(&poisoned, || panic!())
+    /// }));
+    /// assert_eq!(Lazy::try_force_ref(&poisoned), Err(LazyPoisoned));
+    /// ```
+    pub fn try_force_ref(this: &Self) -> Result<&T, LazyPoisoned> {
+        if let Some(value) = this.cell.get() {
+            return Ok(value);
+        }
+        // Checked up front, as a poisoned `Once` panics before it would call the closure, which a panicking
+        // `force_with` leaves in place
+        if this.cell.is_poisoned() || !this.poll_initializer() {
+            return Err(LazyPoisoned);
+        }
+        match this.init.take() {
+            Some(f) => Ok(f.init(&this.cell)),
+            None => Err(LazyPoisoned),
+        }
+    }
+
+    /// Forces the evaluation of this lazy value and returns a pinned reference to the result.
+    ///
+    /// This is sound because the value is constructed in place within the pinned [`Lazy`] and is never
+    /// moved out of it again. It allows `!Unpin` values, such as intrusive structures or async state
+    /// machines, to be lazily initialized in a `static`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// use core::{marker::PhantomPinned, pin::Pin};
+    /// use nospin::Lazy;
+    ///
+    /// struct Node(u32, PhantomPinned);
+    ///
+    /// static NODE: Lazy<Node> = Lazy::new(|| Node(7, PhantomPinned));
+    ///
+    /// let node: Pin<&Node> = Lazy::force_pin(Pin::static_ref(&NODE));
+    /// assert_eq!(node.0, 7);
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    pub fn force_pin(self: Pin<&Self>) -> Pin<&T> {
+        // SAFETY: the value is stored inline, so it could only be moved by moving the `Lazy`, which the pin forbids
+        unsafe { self.map_unchecked(|this| Self::force(this)) }
+    }
+
+    /// Forces the evaluation of this lazy value using `g` rather than the stored initializing
+    /// function, and returns a reference to the result.
+    ///
+    /// If the value has already been initialized then `g` is not called. The stored initializing
+    /// function is left in place either way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::Lazy;
+    ///
+    /// let lazy = Lazy::new(|| 92);
+    ///
+    /// assert_eq!(Lazy::force_with(&lazy, || 7), &7);
+    /// assert_eq!(Lazy::force_with(&lazy, || 8), &7);
+    /// assert_eq!(&*lazy, &7);
+    /// ```
+    pub fn force_with<G: FnOnce() -> T>(this: &Self, g: G) -> &T {
+        this.cell.call_once(g)
+    }
+
+    /// Forces the evaluation of this lazy value and returns a mutable reference to the result.
+    /// This is equivalent to the `DerefMut` impl, but is explicit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::Lazy;
+    ///
+    /// let mut lazy = Lazy::new(|| vec![1]);
+    ///
+    /// Lazy::force_mut(&mut lazy).push(2);
+    /// lazy.push(3);
+    /// assert_eq!(*lazy, [1, 2, 3]);
+    /// ```
+    pub fn force_mut(this: &mut Self) -> &mut T {
+        // The shared borrow taken to initialize the value ends here, leaving us free to borrow
+        // the now initialized `Once` mutably.
+        Self::force(this);
+        match this.cell.get_mut() {
+            Some(value) => value,
+            None => unreachable!(),
+        }
+    }
+
+    /// Consumes this lazy value, returning the result and running the initializing function first if
+    /// it has not been evaluated yet.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if a previous initialization attempt panicked, leaving neither a value
+    /// nor an initializing function to produce one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::Lazy;
+    ///
+    /// let lazy = Lazy::new(|| vec![1]);
+    /// assert_eq!(Lazy::into_value(lazy), [1]);
+    ///
+    /// let mut lazy = Lazy::new(|| vec![1]);
+    /// lazy.push(2);
+    /// assert_eq!(Lazy::into_value(lazy), [1, 2]);
+    /// ```
+    pub fn into_value(this: Self) -> T {
+        let Lazy { cell, init } = this;
+        match Option::from(cell) {
+            Some(value) => value,
+            None => match init.into_inner() {
+                Some(f) => f.call(),
+                None => panic!("{}", LazyPoisoned),
+            },
+        }
     }
 }
 
-impl<T, F: FnOnce() -> T> Deref for Lazy<T, F> {
+impl<T, F: LazyInit<T>> Deref for Lazy<T, F> {
     type Target = T;
 
     fn deref(&self) -> &T {
@@ -109,9 +462,280 @@ impl<T, F: FnOnce() -> T> Deref for Lazy<T, F> {
     }
 }
 
+impl<T, F: LazyInit<T>> DerefMut for Lazy<T, F> {
+    fn deref_mut(&mut self) -> &mut T {
+        Self::force_mut(self)
+    }
+}
+
+impl<T, F: LazyInit<T>> AsRef<T> for Lazy<T, F> {
+    /// Forces the evaluation of this lazy value and returns a reference to the result.
+    ///
+    /// Use [`Lazy::try_as_ref`] to access the value without forcing it.
+    fn as_ref(&self) -> &T {
+        Self::force(self)
+    }
+}
+
+impl<T> Lazy<T, fn() -> T> {
+    /// Creates a new lazy value which has already been initialized with `value`.
+    ///
+    /// This allows a `static` [`Lazy`] to be seeded with a value known at compile time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// use nospin::Lazy;
+    ///
+    /// static ANSWER: Lazy<u32> = Lazy::preinitialized(42);
+    ///
+    /// assert_eq!(*ANSWER, 42);
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    #[inline(always)]
+    pub const fn preinitialized(value: T) -> Self {
+        Lazy {
+            cell: Once::initialized(value),
+            init: Cell::new(None),
+        }
+    }
+}
+
+impl<T> Lazy<T, InPlace<T>> {
+    /// Creates a new lazy value whose initializing function writes the value directly into the storage of the
+    /// [`Lazy`].
+    ///
+    /// The value is then never built on the stack and moved in, as happens with [`Lazy::new`], which matters for
+    /// very large values in a `static` on targets with little stack. See [`Once::call_once_in_place`].
+    ///
+    /// # Safety
+    ///
+    /// `f` must fully initialize the [`MaybeUninit`] it is given before returning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// use core::mem::MaybeUninit;
+    /// use nospin::{Lazy, lazy::InPlace};
+    ///
+    /// fn fill(slot: &mut MaybeUninit<[u32; 4096]>) {
+    ///     let entries = slot.as_mut_ptr().cast::<u32>();
+    ///     for i in 0..4096 {
+    ///         unsafe { entries.add(i).write(i as u32 * 2) };
+    ///     }
+    /// }
+    ///
+    /// static TABLE: Lazy<[u32; 4096], InPlace<[u32; 4096]>> = unsafe { Lazy::new_in_place(fill) };
+    ///
+    /// assert_eq!(TABLE[4095], 8190);
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    #[inline(always)]
+    pub const unsafe fn new_in_place(f: fn(&mut MaybeUninit<T>)) -> Self {
+        Lazy::new(InPlace(f))
+    }
+}
+
+/// The initializing function of a [`Lazy`] created by [`Lazy::new_in_place`], which writes the value in place.
+pub struct InPlace<T>(fn(&mut MaybeUninit<T>));
+
+impl<T> Clone for InPlace<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for InPlace<T> {}
+
+impl<T> fmt::Debug for InPlace<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("InPlace")
+    }
+}
+
+/// An initializing function of a [`Lazy`].
+///
+/// This is implemented by every closure returning `T`, and by [`InPlace`] for values initialized in place. It
+/// cannot be implemented outside of this crate.
+pub trait LazyInit<T>: sealed::Sealed<T> {}
+
+impl<T, F: sealed::Sealed<T>> LazyInit<T> for F {}
+
+mod sealed {
+    use super::{InPlace, MaybeUninit, Once};
+
+    pub trait Sealed<T> {
+        /// Initializes `cell` with the value, returning a reference to it.
+        fn init(self, cell: &Once<T>) -> &T;
+
+        /// Returns the value.
+        fn call(self) -> T;
+    }
+
+    impl<T, F: FnOnce() -> T> Sealed<T> for F {
+        fn init(self, cell: &Once<T>) -> &T {
+            cell.call_once(self)
+        }
+
+        fn call(self) -> T {
+            self()
+        }
+    }
+
+    impl<T> Sealed<T> for InPlace<T> {
+        fn init(self, cell: &Once<T>) -> &T {
+            // SAFETY: `Lazy::new_in_place` requires the function to initialize the value
+            unsafe { cell.call_once_in_place(self.0) }
+        }
+
+        fn call(self) -> T {
+            let mut value = MaybeUninit::uninit();
+            (self.0)(&mut value);
+            // SAFETY: as above
+            unsafe { value.assume_init() }
+        }
+    }
+}
+
 impl<T: Default> Default for Lazy<T, fn() -> T> {
     /// Creates a new lazy value using `Default` as the initializing function.
     fn default() -> Self {
         Self::new(T::default)
     }
 }
+
+/// A [`Lazy`] value that runs a deinitializing function on its value before it is dropped.
+///
+/// The deinitializing function only runs if the value was initialized, so dropping a [`LazyWithDrop`] that was never
+/// forced is a no-op. This allows cleanup such as powering down a peripheral to be tied to the lazy value that set it
+/// up. Note that, as with any other type, the value of a `static` is never dropped.
+///
+/// # Examples
+///
+/// ```
+/// use core::cell::Cell;
+/// use nospin::lazy::LazyWithDrop;
+///
+/// struct Peripheral<'a> {
+///     powered: &'a Cell<bool>,
+/// }
+///
+/// let powered = Cell::new(false);
+/// let power_up = || {
+///     powered.set(true);
+///     Peripheral { powered: &powered }
+/// };
+/// let power_down = |p: &mut Peripheral| p.powered.set(false);
+///
+/// let peripheral = LazyWithDrop::new(power_up, power_down);
+/// LazyWithDrop::force(&peripheral);
+/// assert!(powered.get());
+/// drop(peripheral);
+/// assert!(!powered.get());
+///
+/// // Never forced, so never powered down
+/// let peripheral = LazyWithDrop::new(power_up, |_: &mut Peripheral| unreachable!());
+/// drop(peripheral);
+/// ```
+pub struct LazyWithDrop<T, F = fn() -> T, D: FnOnce(&mut T) = fn(&mut T)> {
+    lazy: Lazy<T, F>,
+    deinit: Option<D>,
+}
+
+impl<T: fmt::Debug, F, D: FnOnce(&mut T)> fmt::Debug for LazyWithDrop<T, F, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_tuple("LazyWithDrop");
+        let d = if let Some(x) = self.lazy.cell.get() {
+            d.field(&x)
+        } else {
+            d.field(&format_args!("<uninit>"))
+        };
+        d.finish()
+    }
+}
+
+impl<T, F, D: FnOnce(&mut T)> LazyWithDrop<T, F, D> {
+    /// Creates a new lazy value with the given initializing and deinitializing functions.
+    #[inline(always)]
+    pub const fn new(f: F, d: D) -> Self {
+        LazyWithDrop {
+            lazy: Lazy::new(f),
+            deinit: Some(d),
+        }
+    }
+
+    /// Returns the underlying [`Lazy`], for access to the rest of its API.
+    ///
+    /// This is an associated function that needs to be used as `LazyWithDrop::as_lazy(...)`, so as not to conflict
+    /// with a method of the same name on the value.
+    #[inline(always)]
+    pub fn as_lazy(this: &Self) -> &Lazy<T, F> {
+        &this.lazy
+    }
+}
+
+impl<T, F: LazyInit<T>, D: FnOnce(&mut T)> LazyWithDrop<T, F, D> {
+    /// Forces the evaluation of this lazy value and returns a reference to the result, as with
+    /// [`Lazy::force`].
+    pub fn force(this: &Self) -> &T {
+        Lazy::force(&this.lazy)
+    }
+}
+
+impl<T, F: LazyInit<T>, D: FnOnce(&mut T)> Deref for LazyWithDrop<T, F, D> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        Lazy::force(&self.lazy)
+    }
+}
+
+impl<T, F: LazyInit<T>, D: FnOnce(&mut T)> DerefMut for LazyWithDrop<T, F, D> {
+    fn deref_mut(&mut self) -> &mut T {
+        Lazy::force_mut(&mut self.lazy)
+    }
+}
+
+impl<T, F, D: FnOnce(&mut T)> Drop for LazyWithDrop<T, F, D> {
+    fn drop(&mut self) {
+        if let (Some(value), Some(deinit)) = (self.lazy.cell.get_mut(), self.deinit.take()) {
+            deinit(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(panic = "unwind")]
+    #[test]
+    fn clone_keeps_init_on_panic() {
+        use std::panic;
+
+        struct PanicOnClone;
+
+        impl Clone for PanicOnClone {
+            fn clone(&self) -> Self {
+                panic!("clone")
+            }
+        }
+
+        let token = PanicOnClone;
+        let lazy = Lazy::new(move || {
+            let _ = &token;
+            3
+        });
+        let clone = panic::catch_unwind(panic::AssertUnwindSafe(|| lazy.clone()));
+        assert!(clone.is_err());
+        assert_eq!(*lazy, 3);
+    }
+}
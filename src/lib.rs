@@ -7,7 +7,7 @@
 //!
 //! # Features
 //!
-//! - `Mutex`, `RwLock`, `Once`/`SyncOnceCell`, and `SyncLazy` equivalents
+//! - `Mutex`, `ReentrantMutex`, `RwLock`, `Once`/`SyncOnceCell`, and `SyncLazy` equivalents
 //!
 //! - Support for `no_std` environments
 //!
@@ -29,8 +29,87 @@
 //! The crate comes with a few feature flags that you may wish to use.
 //!
 //! - `lock_api` enables support for [`lock_api`](https://crates.io/crates/lock_api)
+//!
+//! - `reentrant_mutex` enables `ReentrantMutex`, a mutex that the context holding it may lock again
+//!
+//! - `saturating_readers` makes `RwLock` stop counting readers beyond its cap instead of panicking. A lock that has
+//!   saturated refuses all writers from then on
+//!
+//! - `debug_counters` adds `Once::init_count` to help track down re-entrant initialization
+//!
+//! - `verbose_debug` wraps the `Debug` output of guards in the guard's name, e.g. `RwLockWriteGuard(5)`,
+//!   rather than forwarding to the inner value
+//!
+//! - `small_state` backs `RwLock` with a `u16` rather than a `usize`, shrinking it on targets with a wider
+//!   `usize` at the cost of a much lower reader cap (8191)
+//!
+//! - `async` adds `Once::poll_initialized`, letting a task on a single-threaded executor await another task's
+//!   initialization of a `Once`
+//!
+//! - `debug_checks` keeps the invariant checks that catch misuse of the unsafe APIs (such as `force_unlock`)
+//!   enabled in release builds, where they would otherwise be compiled out
+//!
+//! - `strict_send_sync` removes the `Sync` (and guard `Send`) impls from the primitives, so the compiler rejects any
+//!   attempt to share them between threads. They can then no longer be used in `static`s. Unlike the other
+//!   features it is not additive: since features are unified across the dependency graph, enabling it anywhere breaks
+//!   every crate in the build that keeps these primitives in `static`s, so it should only be enabled by the final
+//!   binary
+//!
+//! - `embedded_io` implements the [`embedded-io`](https://crates.io/crates/embedded-io) `Read` and `Write` traits for
+//!   `MutexGuard` and `RwLockWriteGuard` when the locked device implements them
+//!
+//! - `arc_lock` adds `Mutex::lock_owned`, returning a guard that holds an `Arc` of the lock rather than borrowing it
+//!
+//! - `lock_order` panics in debug builds when `Mutex`es and `RwLock`s are nested in an order inconsistent with an
+//!   earlier nesting, catching potential lock ordering bugs in re-entrant code. It does nothing in release builds.
+//!   Its records are not synchronized, so it must only be enabled when the locks are used from a single context: one
+//!   thread, with no interrupt handlers acquiring them
+//!
+//! - `spin_compat` fills in the remaining `spin` API used by ported code: `Mutex::as_mut_ptr`, `MutexGuard::leak`,
+//!   `Once::try_into_inner`, and the `SpinMutex` and `SpinMutexGuard` aliases. Methods that would spin in `spin`
+//!   panic instead, as no other thread could ever release the lock
+//!
+//! - `branded` adds `Mutex::scope`, which locks the mutex for the duration of a closure and passes it a guard
+//!   branded with an invariant lifetime, so that the guard cannot be returned or stored outside of the closure
+//!
+//! - `cortex-m` disables interrupts with [`cortex-m`](https://crates.io/crates/cortex-m) while a `Mutex` or `RwLock`
+//!   updates its state, so that an interrupt handler preempting the update sees the lock as either held or free.
+//!   Interrupts are only disabled for the update itself, not while the lock is held, and only on Cortex-M targets
+//!   (`target_arch = "arm"` with `target_os = "none"`). On any other target the feature silently does nothing
 extern crate alloc;
 
+// Checks an invariant of the primitives in debug builds, or in any build with the `debug_checks` feature. These
+// guard against misuse of the unsafe APIs, so unlike other assertions they are worth keeping in the field.
+#[allow(unused_macros)]
+macro_rules! debug_check {
+    ($($arg:tt)*) => {
+        if cfg!(any(debug_assertions, feature = "debug_checks")) {
+            assert!($($arg)*);
+        }
+    };
+}
+
+// As `debug_check!`, but for `assert_eq!`.
+#[allow(unused_macros)]
+macro_rules! debug_check_eq {
+    ($($arg:tt)*) => {
+        if cfg!(any(debug_assertions, feature = "debug_checks")) {
+            assert_eq!($($arg)*);
+        }
+    };
+}
+
+// Runs `f`, which updates the state of a lock, with interrupts disabled if the `cortex-m` feature is enabled.
+#[cfg(any(feature = "mutex", feature = "rwlock"))]
+#[inline(always)]
+fn interrupt_free<R>(f: impl FnOnce() -> R) -> R {
+    // `cortex_m::interrupt::free` panics on targets other than Cortex-M, such as the host running the tests
+    #[cfg(all(feature = "cortex-m", target_arch = "arm", target_os = "none"))]
+    return cortex_m::interrupt::free(|_| f());
+    #[cfg(not(all(feature = "cortex-m", target_arch = "arm", target_os = "none")))]
+    f()
+}
+
 #[cfg(feature = "lazy")]
 #[cfg_attr(docsrs, doc(cfg(feature = "lazy")))]
 pub mod lazy;
@@ -52,12 +131,62 @@ pub mod once;
 #[cfg(feature = "once")]
 pub use once::Once;
 
+#[cfg(feature = "reentrant_mutex")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reentrant_mutex")))]
+pub mod reentrant_mutex;
+
+#[cfg(feature = "reentrant_mutex")]
+pub use reentrant_mutex::{ReentrantMutex, ReentrantMutexGuard};
+
 #[cfg(feature = "rwlock")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rwlock")))]
 pub mod rwlock;
 
 #[cfg(feature = "rwlock")]
-pub use rwlock::{RwLock, RwLockReadGuard, RwLockUpgradableGuard, RwLockWriteGuard};
+pub use rwlock::{
+    MappedRwLockReadGuard, MappedRwLockWriteGuard, RwLock, RwLockReadGuard, RwLockUpgradableGuard,
+    RwLockWriteGuard,
+};
+
+#[cfg(all(feature = "lock_order", any(feature = "mutex", feature = "rwlock")))]
+mod lock_order;
+
+pub mod traits;
+
+/// Convenience re-exports of the commonly used types in this crate.
+///
+/// ```
+/// # #[cfg(all(feature = "mutex", feature = "once", not(feature = "strict_send_sync")))]
+/// # fn main() {
+/// use nospin::prelude::*;
+///
+/// static LOCK: Mutex<u32> = Mutex::new(0);
+/// static ONCE: Once<u32> = Once::new();
+///
+/// *LOCK.lock() = *ONCE.call_once(|| 42);
+/// # }
+/// # #[cfg(not(all(feature = "mutex", feature = "once", not(feature = "strict_send_sync"))))]
+/// # fn main() {}
+/// ```
+pub mod prelude {
+    #[cfg(feature = "lazy")]
+    pub use crate::lazy::Lazy;
+
+    #[cfg(feature = "mutex")]
+    pub use crate::mutex::{Mutex, MutexGuard};
+
+    #[cfg(feature = "once")]
+    pub use crate::once::Once;
+
+    #[cfg(feature = "reentrant_mutex")]
+    pub use crate::reentrant_mutex::{ReentrantMutex, ReentrantMutexGuard};
+
+    #[cfg(feature = "rwlock")]
+    pub use crate::rwlock::{
+        MappedRwLockReadGuard, MappedRwLockWriteGuard, RwLock, RwLockReadGuard,
+        RwLockUpgradableGuard, RwLockWriteGuard,
+    };
+}
 
 /// Spin synchronisation primitives, but compatible with [`lock_api`](https://crates.io/crates/lock_api).
 #[cfg(feature = "lock_api")]
@@ -105,4 +234,98 @@ pub mod lock_api {
     #[cfg_attr(docsrs, doc(cfg(feature = "rwlock")))]
     pub type MappedRwLockWriteGuard<'a, T> =
         lock_api_crate::MappedRwLockWriteGuard<'a, crate::RwLock<()>, T>;
+
+    /// Creates a [`lock_api::Mutex`](Mutex) holding the default value of `T`.
+    ///
+    /// This is the same as `Mutex::default()`, as `lock_api` implements `Default` for its mutex whenever `T` does,
+    /// so the aliased type can also be used in `#[derive(Default)]` structs.
+    ///
+    /// ```
+    /// use nospin::lock_api::{Mutex, default_mutex};
+    ///
+    /// #[derive(Default)]
+    /// struct State {
+    ///     hits: Mutex<u32>,
+    /// }
+    ///
+    /// assert_eq!(*default_mutex::<u32>().lock(), 0);
+    /// assert_eq!(*State::default().hits.lock(), 0);
+    /// ```
+    #[cfg(feature = "mutex")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mutex")))]
+    pub fn default_mutex<T: Default>() -> Mutex<T> {
+        Mutex::default()
+    }
+
+    /// Creates a [`lock_api::RwLock`](RwLock) holding the default value of `T`.
+    ///
+    /// This is the same as `RwLock::default()`, as `lock_api` implements `Default` for its rwlock whenever `T` does,
+    /// so the aliased type can also be used in `#[derive(Default)]` structs.
+    ///
+    /// ```
+    /// use nospin::lock_api::{RwLock, default_rwlock};
+    ///
+    /// #[derive(Default)]
+    /// struct Config {
+    ///     name: RwLock<String>,
+    /// }
+    ///
+    /// assert_eq!(*default_rwlock::<String>().read(), "");
+    /// assert_eq!(*Config::default().name.read(), "");
+    /// ```
+    #[cfg(feature = "rwlock")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rwlock")))]
+    pub fn default_rwlock<T: Default>() -> RwLock<T> {
+        RwLock::default()
+    }
+
+    /// Creates a [`lock_api::Mutex`](Mutex) in a const context.
+    ///
+    /// This expands to `lock_api`'s `const_new`, which is `const` on every toolchain, rather than relying on
+    /// `Mutex::new` being a `const fn`. Any `lock_api` 0.4 release is sufficient.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// static LOCK: nospin::lock_api::Mutex<u32> = nospin::const_mutex!(0);
+    ///
+    /// *LOCK.lock() += 1;
+    /// assert_eq!(*LOCK.lock(), 1);
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "mutex")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mutex")))]
+    #[macro_export]
+    macro_rules! const_mutex {
+        ($value:expr) => {
+            $crate::lock_api::Mutex::const_new($crate::Mutex::<()>::INIT, $value)
+        };
+    }
+
+    /// Creates a [`lock_api::RwLock`](RwLock) in a const context.
+    ///
+    /// This expands to `lock_api`'s `const_new`, which is `const` on every toolchain, rather than relying on
+    /// `RwLock::new` being a `const fn`. Any `lock_api` 0.4 release is sufficient.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// static LOCK: nospin::lock_api::RwLock<u32> = nospin::const_rwlock!(0);
+    ///
+    /// *LOCK.write() += 1;
+    /// assert_eq!(*LOCK.read(), 1);
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "rwlock")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rwlock")))]
+    #[macro_export]
+    macro_rules! const_rwlock {
+        ($value:expr) => {
+            $crate::lock_api::RwLock::const_new($crate::RwLock::<()>::INIT, $value)
+        };
+    }
 }
@@ -0,0 +1,292 @@
+//! Checking of the order in which nested locks are acquired, enabled by the `lock_order` feature.
+//!
+//! Whenever a [`Mutex`](crate::Mutex) or [`RwLock`](crate::RwLock) is acquired while others are held, the order in
+//! which they are nested is recorded. Acquiring a lock while holding one that was earlier acquired inside it,
+//! directly or through other locks, is then reported as a potential ordering violation: it means two code paths nest
+//! the same locks in opposite orders, which would deadlock with threads and, in single-threaded re-entrant code,
+//! panics whenever the paths meet. The first nesting observed therefore defines the expected order. In release
+//! builds all of this compiles away to nothing.
+//!
+//! Locks are told apart by an id given to them the first time they are acquired rather than at construction, so that
+//! they keep their `const` constructors.
+//!
+//! The records are global and not synchronized, so the locks must only be used from a single context: one thread,
+//! with no interrupt handlers acquiring them.
+#[cfg(debug_assertions)]
+use {
+    alloc::vec::Vec,
+    core::{
+        cell::RefCell,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+};
+
+#[cfg(all(debug_assertions, not(test)))]
+use core::cell::Cell;
+
+/// The id of a lock, assigned lazily the first time it is acquired.
+pub(crate) struct LockId {
+    // Only ever loaded and stored outside of tests, which every target supports
+    #[cfg(debug_assertions)]
+    id: AtomicUsize,
+}
+
+impl LockId {
+    pub(crate) const fn new() -> Self {
+        LockId {
+            #[cfg(debug_assertions)]
+            id: AtomicUsize::new(0),
+        }
+    }
+
+    #[cfg(all(debug_assertions, test))]
+    fn get(&self, _held: &Held) -> usize {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+        match self.id.load(Ordering::Relaxed) {
+            0 => {
+                let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+                // Another thread may have assigned an id in the meantime, in which case that one is kept
+                match self
+                    .id
+                    .compare_exchange(0, id, Ordering::Relaxed, Ordering::Relaxed)
+                {
+                    Ok(_) => id,
+                    Err(id) => id,
+                }
+            }
+            id => id,
+        }
+    }
+
+    #[cfg(all(debug_assertions, not(test)))]
+    fn get(&self, held: &Held) -> usize {
+        match self.id.load(Ordering::Relaxed) {
+            0 => {
+                let id = held.next_id.get() + 1;
+                held.next_id.set(id);
+                self.id.store(id, Ordering::Relaxed);
+                id
+            }
+            id => id,
+        }
+    }
+}
+
+// The locks currently held, as (address, id) pairs in the order they were acquired, and the nestings observed so
+// far, as (outer id, inner id) pairs. The address identifies the lock when it is released, as guards only keep a
+// reference to the lock state rather than to the lock itself.
+#[cfg(debug_assertions)]
+struct Held {
+    #[cfg(not(test))]
+    next_id: Cell<usize>,
+    locks: RefCell<Vec<(usize, usize)>>,
+    nestings: RefCell<Vec<(usize, usize)>>,
+}
+
+#[cfg(debug_assertions)]
+impl Held {
+    const fn new() -> Self {
+        Held {
+            #[cfg(not(test))]
+            next_id: Cell::new(0),
+            locks: RefCell::new(Vec::new()),
+            nestings: RefCell::new(Vec::new()),
+        }
+    }
+
+    // Whether `inner` has been acquired inside `outer`, directly or through other locks
+    fn nested_inside(&self, outer: usize, inner: usize) -> bool {
+        let nestings = self.nestings.borrow();
+        let mut pending = Vec::from([outer]);
+        let mut visited = Vec::new();
+        while let Some(id) = pending.pop() {
+            for &(_, next) in nestings.iter().filter(|&&(from, _)| from == id) {
+                if next == inner {
+                    return true;
+                }
+                if !visited.contains(&next) {
+                    visited.push(next);
+                    pending.push(next);
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(all(debug_assertions, not(test)))]
+fn with_held<R>(f: impl FnOnce(&Held) -> R) -> R {
+    struct Global(Held);
+
+    // There are no thread locals without `std`, and the feature is documented to require a single context
+    unsafe impl Sync for Global {}
+
+    static HELD: Global = Global(Held::new());
+    f(&HELD.0)
+}
+
+// The tests run on several threads at once, each holding its own locks
+#[cfg(all(debug_assertions, test))]
+fn with_held<R>(f: impl FnOnce(&Held) -> R) -> R {
+    std::thread_local! {
+        static HELD: Held = const { Held::new() };
+    }
+    HELD.with(f)
+}
+
+/// Panics if a lock that has been acquired inside the lock with `id` is currently held, and otherwise records the
+/// nesting of the lock inside those held. Called before a lock is acquired by the panicking lock functions, while the
+/// `try_` variants, which cannot deadlock, skip it.
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+#[inline(always)]
+pub(crate) fn check(id: &LockId) {
+    #[cfg(debug_assertions)]
+    {
+        let violation = with_held(|held| {
+            let id = id.get(held);
+            let outer: Vec<usize> = held
+                .locks
+                .borrow()
+                .iter()
+                .map(|&(_, held_id)| held_id)
+                .filter(|&held_id| held_id != id)
+                .collect();
+            if let Some(&held_id) = outer
+                .iter()
+                .find(|&&held_id| held.nested_inside(id, held_id))
+            {
+                return Some((id, held_id));
+            }
+            let mut nestings = held.nestings.borrow_mut();
+            for held_id in outer {
+                if !nestings.contains(&(held_id, id)) {
+                    nestings.push((held_id, id));
+                }
+            }
+            None
+        });
+        if let Some((id, held_id)) = violation {
+            panic!(
+                "Potential lock order violation: acquiring lock {id} while holding lock {held_id}"
+            );
+        }
+    }
+}
+
+/// Records that the lock with state at `addr` has been acquired.
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+#[inline(always)]
+pub(crate) fn acquired<S>(addr: *const S, id: &LockId) {
+    #[cfg(debug_assertions)]
+    with_held(|held| {
+        let id = id.get(held);
+        held.locks.borrow_mut().push((addr as usize, id));
+    });
+}
+
+/// Records another acquisition of a lock that is already held, such as a further read of a [`RwLock`](crate::RwLock).
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+#[inline(always)]
+pub(crate) fn reacquired<S>(addr: *const S) {
+    #[cfg(debug_assertions)]
+    with_held(|held| {
+        let mut locks = held.locks.borrow_mut();
+        if let Some(&entry) = locks.iter().rev().find(|&&(a, _)| a == addr as usize) {
+            locks.push(entry);
+        }
+    });
+}
+
+/// Records that one acquisition of the lock with state at `addr` has been released.
+///
+/// Locks that are held without having been recorded, such as those created already locked, are ignored.
+#[cfg_attr(not(debug_assertions), allow(unused_variables))]
+#[inline(always)]
+pub(crate) fn released<S>(addr: *const S) {
+    #[cfg(debug_assertions)]
+    with_held(|held| {
+        let mut locks = held.locks.borrow_mut();
+        if let Some(index) = locks.iter().rposition(|&(a, _)| a == addr as usize) {
+            locks.remove(index);
+        }
+    });
+}
+
+#[cfg(all(test, debug_assertions, feature = "mutex", feature = "rwlock"))]
+mod tests {
+    use std::panic;
+
+    use crate::{Mutex, RwLock};
+
+    #[test]
+    fn consistent_order() {
+        let a = Mutex::new(0);
+        let b = RwLock::new(0);
+
+        for _ in 0..2 {
+            let _a = a.lock();
+            let _b1 = b.read();
+            let _b2 = b.read();
+        }
+
+        // Locks held one at a time can be taken in any order
+        drop(b.write());
+        drop(a.lock());
+    }
+
+    #[test]
+    fn first_nesting_defines_order() {
+        let a = Mutex::new(0);
+        let b = RwLock::new(0);
+
+        // `b` is seen before `a`, but nested inside it
+        drop(b.read());
+        let _a = a.lock();
+        let _b = b.read();
+    }
+
+    #[test]
+    fn indirect_inversion() {
+        let a = Mutex::new(0);
+        let b = Mutex::new(0);
+        let c = RwLock::new(0);
+        drop((a.lock(), b.lock()));
+        drop((b.lock(), c.write()));
+
+        let gc = c.read();
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            a.lock();
+        }));
+        assert!(t.is_err());
+        drop(gc);
+    }
+
+    #[test]
+    fn inverted_order() {
+        let a = Mutex::new(0);
+        let b = RwLock::new(0);
+        drop((a.lock(), b.write()));
+
+        let gb = b.write();
+        // Trying a lock cannot deadlock, so is not checked
+        assert!(a.try_lock().is_some());
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            a.lock();
+        }));
+        let msg = t.unwrap_err().downcast::<String>().unwrap();
+        assert!(msg.starts_with("Potential lock order violation"));
+        drop(gb);
+
+        // Downgrading keeps the lock recorded as held, while releasing the outer lock first is fine
+        let ga = a.lock();
+        let gb = b.upgradeable_read().downgrade();
+        drop(ga);
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            a.lock();
+        }));
+        assert!(t.is_err());
+        drop(gb);
+        drop(a.lock());
+    }
+}
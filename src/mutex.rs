@@ -1,12 +1,24 @@
 //! Locks that have the same behaviour as a mutex.
 use {
-    alloc::fmt,
+    crate::traits::{Guard, GuardMut, TryLock},
+    alloc::{boxed::Box, fmt},
     core::{
+        alloc::Layout,
         cell::UnsafeCell,
+        cmp,
+        marker::PhantomData,
+        mem::forget,
         ops::{Deref, DerefMut},
+        ptr::NonNull,
     },
 };
 
+#[cfg(feature = "arc_lock")]
+use alloc::sync::Arc;
+
+#[cfg(feature = "lock_order")]
+use crate::lock_order::{self, LockId};
+
 /// A Mutex that is NOT thread safe allow uncontested access to mutable data
 /// suitable only for single-threaded environments.
 ///
@@ -24,8 +36,12 @@ use {
 /// let answer = *lock.lock();
 /// assert_eq!(answer, 2);
 /// ```
+// `repr(C)` so that the layout relied upon by `Mutex::from_ptr_checked` is guaranteed
+#[repr(C)]
 pub struct Mutex<T: ?Sized> {
     locked: UnsafeCell<bool>,
+    #[cfg(feature = "lock_order")]
+    lock_id: LockId,
     data: UnsafeCell<T>,
 }
 
@@ -40,6 +56,76 @@ impl<T: fmt::Debug> fmt::Debug for Mutex<T> {
     }
 }
 
+impl<T: ?Sized> fmt::Pointer for Mutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.data.get(), f)
+    }
+}
+
+// Releases the lock whose flag is `locked`.
+#[inline(always)]
+fn release(locked: &UnsafeCell<bool>) {
+    #[cfg(feature = "lock_order")]
+    lock_order::released(locked);
+    unsafe { *locked.get() = false }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    // Marks the lock as held if it is free, returning whether it was.
+    #[inline(always)]
+    fn try_mark_locked(&self) -> bool {
+        let acquired = crate::interrupt_free(|| unsafe {
+            let locked = &mut *self.locked.get();
+            !core::mem::replace(locked, true)
+        });
+        #[cfg(feature = "lock_order")]
+        if acquired {
+            lock_order::acquired(&self.locked, &self.lock_id);
+        }
+        acquired
+    }
+
+    // Locking the same mutex twice would panic, so comparing a mutex with itself compares its data through one guard.
+    fn with_pair<R>(&self, other: &Self, f: impl FnOnce(&T, &T) -> R) -> R {
+        let this = self.lock();
+        if core::ptr::eq(self, other) {
+            return f(&this, &this);
+        }
+        // Either lock may be compared first, so the second is not subject to a lock order check
+        let other = other.try_lock().expect("Mutex is already locked");
+        f(&this, &other)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for Mutex<T> {
+    /// Compares the data of two locks.
+    ///
+    /// This locks both mutexes for the duration of the comparison, and so will panic if either is already held.
+    fn eq(&self, other: &Self) -> bool {
+        self.with_pair(other, |a, b| a == b)
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for Mutex<T> {}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for Mutex<T> {
+    /// Compares the data of two locks.
+    ///
+    /// This locks both mutexes for the duration of the comparison, and so will panic if either is already held.
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.with_pair(other, |a, b| a.partial_cmp(b))
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for Mutex<T> {
+    /// Compares the data of two locks.
+    ///
+    /// This locks both mutexes for the duration of the comparison, and so will panic if either is already held.
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.with_pair(other, |a, b| a.cmp(b))
+    }
+}
+
 impl<T: Default> Default for Mutex<T> {
     fn default() -> Self {
         Self::new(Default::default())
@@ -52,7 +138,36 @@ impl<T> From<T> for Mutex<T> {
     }
 }
 
+impl<A, C: Extend<A>> Extend<A> for Mutex<C> {
+    /// Extends the inner collection with the contents of an iterator.
+    ///
+    /// As the mutex is borrowed mutably, no locking is needed.
+    fn extend<I: IntoIterator<Item = A>>(&mut self, iter: I) {
+        self.get_mut().extend(iter)
+    }
+}
+
+impl<A, C: FromIterator<A>> FromIterator<A> for Mutex<C> {
+    fn from_iter<I: IntoIterator<Item = A>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<C: IntoIterator> IntoIterator for Mutex<C> {
+    type Item = C::Item;
+    type IntoIter = C::IntoIter;
+
+    /// Consumes the mutex and iterates over the inner collection by value.
+    ///
+    /// As the mutex is consumed, no locking is needed.
+    fn into_iter(self) -> C::IntoIter {
+        self.into_inner().into_iter()
+    }
+}
+
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
 
 impl<T> Mutex<T> {
@@ -61,6 +176,8 @@ impl<T> Mutex<T> {
     /// # Example
     ///
     /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
     /// use nospin::Mutex;
     ///
     /// static MUTEX: Mutex<()> = Mutex::new(());
@@ -70,15 +187,115 @@ impl<T> Mutex<T> {
     ///     // do something with lock
     ///     drop(lock);
     /// }
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
     /// ```
     #[inline(always)]
     pub const fn new(data: T) -> Mutex<T> {
         Mutex {
             locked: UnsafeCell::new(false),
+            #[cfg(feature = "lock_order")]
+            lock_id: LockId::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Creates a new [`Mutex`] wrapping the supplied data that is already locked.
+    ///
+    /// No guard corresponds to the held lock, so it can only be released with
+    /// [`Mutex::force_unlock`]. This is mostly useful for exercising the contended paths of code,
+    /// or for handing a pre-locked mutex to FFI.
+    ///
+    /// As this is `const`, it can also initialize a `static` that must begin claimed, such as a
+    /// resource owned by the boot code until its setup has completed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// use nospin::Mutex;
+    ///
+    /// static UART: Mutex<u32> = Mutex::new_locked(0);
+    ///
+    /// fn early_boot() {
+    ///     // Nothing else may use the UART until it has been configured
+    ///     unsafe { *UART.get_mut_unchecked() = 115_200 };
+    ///     unsafe { UART.force_unlock() };
+    /// }
+    ///
+    /// assert!(UART.try_lock().is_none());
+    /// early_boot();
+    /// assert_eq!(*UART.lock(), 115_200);
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    #[inline(always)]
+    pub const fn new_locked(data: T) -> Mutex<T> {
+        Mutex {
+            locked: UnsafeCell::new(true),
+            #[cfg(feature = "lock_order")]
+            lock_id: LockId::new(),
             data: UnsafeCell::new(data),
         }
     }
 
+    /// Reinterprets a pointer to memory holding a [`Mutex`], such as a buffer shared with C code, as a lock,
+    /// returning `None` if `ptr` is null or not aligned for a `Mutex<T>`.
+    ///
+    /// The [`Mutex`] is laid out as a C struct holding a `bool` lock flag at offset 0 followed by the data,
+    /// which is at the offset of the flag padded up to the alignment of `T`. The size and alignment are
+    /// those of that struct, as reported by [`core::mem::size_of`] and [`core::mem::align_of`] for
+    /// `Mutex<T>`. With the `lock_order` feature the lock carries an additional field between the two, so
+    /// that feature must not be used with locks shared over FFI.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to `size_of::<Mutex<T>>()` bytes that are valid for reads and writes for the rest of
+    /// the program and hold an initialized [`Mutex`]: a lock flag of `0` (unlocked) or `1` (locked) and a
+    /// valid `T`. The memory must only be accessed through the returned reference from then on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nospin::Mutex;
+    ///
+    /// let lock = Box::leak(Box::new(Mutex::new(7u32)));
+    /// let ptr = lock as *mut Mutex<u32> as *mut u32;
+    ///
+    /// let shared = unsafe { Mutex::from_ptr_checked(ptr) }.unwrap();
+    /// assert_eq!(*shared.lock(), 7);
+    ///
+    /// let misaligned = ptr.cast::<u8>().wrapping_add(1).cast::<u32>();
+    /// assert!(unsafe { Mutex::from_ptr_checked(misaligned) }.is_none());
+    /// ```
+    #[inline(always)]
+    pub unsafe fn from_ptr_checked(ptr: *mut T) -> Option<&'static Mutex<T>> {
+        let ptr = ptr.cast::<Mutex<T>>();
+        if ptr.is_null() || !ptr.is_aligned() {
+            return None;
+        }
+        Some(unsafe { &*ptr })
+    }
+
+    /// Creates a new heap-allocated [`Mutex`] wrapping the supplied data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::fmt::Display;
+    /// use nospin::Mutex;
+    ///
+    /// let lock: Box<Mutex<dyn Display>> = Mutex::boxed(42);
+    /// assert_eq!(lock.lock().to_string(), "42");
+    /// ```
+    #[inline(always)]
+    pub fn boxed(data: T) -> Box<Self> {
+        Box::new(Self::new(data))
+    }
+
     /// Consumes this [`Mutex`] and unwraps the underlying data.
     ///
     /// # Example
@@ -94,136 +311,1163 @@ impl<T> Mutex<T> {
         let Mutex { data, .. } = self;
         data.into_inner()
     }
+
+    /// Replaces the data with `value` and leaves the [`Mutex`] unlocked, returning the old data.
+    ///
+    /// As this takes `&mut self`, no guard can be alive, so any lock still held must have been leaked, for instance
+    /// with [`core::mem::forget`] or [`Mutex::new_locked`]. This makes it a way to recycle a [`Mutex`] regardless of
+    /// how it was left.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut lock = nospin::Mutex::new_locked(vec![1, 2]);
+    /// assert_eq!(lock.reset(Vec::new()), [1, 2]);
+    /// assert!(lock.lock().is_empty());
+    /// ```
+    pub fn reset(&mut self, value: T) -> T {
+        if *self.locked.get_mut() {
+            release(&self.locked);
+        }
+        core::mem::replace(self.data.get_mut(), value)
+    }
+
+    /// Consumes this [`Mutex`], transforming the underlying data with `f` into a new, unlocked [`Mutex`].
+    ///
+    /// As the mutex is consumed, no locking is needed to access the data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let lock = nospin::Mutex::new(vec![1, 2, 3]);
+    /// let lock = lock.map(|v| v.len());
+    /// assert_eq!(*lock.lock(), 3);
+    /// ```
+    #[inline(always)]
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Mutex<U> {
+        Mutex::new(f(self.into_inner()))
+    }
+}
+
+impl Mutex<()> {
+    /// Initialization constant of [`Mutex`].
+    ///
+    /// This is useful for building tables of locks in a `static`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// static LOCKS: [nospin::Mutex<()>; 16] = [nospin::Mutex::INIT; 16];
+    ///
+    /// let guard = LOCKS[3].lock();
+    /// assert!(LOCKS[3].is_locked());
+    /// assert!(!LOCKS[4].is_locked());
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    #[allow(clippy::declare_interior_mutable_const)]
+    pub const INIT: Self = Self::new(());
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Force unlock this [`Mutex`].
+    ///
+    /// # Safety
+    ///
+    /// This is *extremely* unsafe if the lock is not held by the current
+    /// thread. However, this can be useful in some instances for exposing the
+    /// lock to FFI that doesn't know how to deal with RAII.
+    #[inline(always)]
+    pub unsafe fn force_unlock(&self) {
+        debug_check!(
+            self.is_locked(),
+            "Attempted to force unlock an unlocked Mutex"
+        );
+        release(&self.locked);
+    }
+
+    /// Takes over a held lock, returning a guard for it while the original holder still believes it holds
+    /// the lock. Returns `None` if the lock is not held, in which case [`Mutex::try_lock`] should be used.
+    ///
+    /// This is a last-resort escape hatch for crash-recovery code that must reclaim a lock whose holder was
+    /// terminated, such as a task that was aborted mid-operation. The lock stays held until the returned guard
+    /// is dropped, and dropping the original guard afterwards would release it a second time.
+    ///
+    /// # Safety
+    ///
+    /// The original holder of the lock must never access the data or release the lock again, not even by
+    /// dropping its guard. Otherwise both it and the returned guard hold a mutable reference to the data,
+    /// which is undefined behaviour. The data may also have been left in an inconsistent state by the
+    /// interrupted holder.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let lock = nospin::Mutex::new(0);
+    ///
+    /// // A holder that will never release the lock
+    /// core::mem::forget(lock.lock());
+    ///
+    /// let mut guard = unsafe { lock.steal() }.unwrap();
+    /// *guard = 1;
+    /// drop(guard);
+    /// assert!(!lock.is_locked());
+    /// ```
+    #[inline(always)]
+    pub unsafe fn steal(&self) -> Option<MutexGuard<'_, T>> {
+        if !self.is_locked() {
+            return None;
+        }
+        #[cfg(feature = "lock_order")]
+        lock_order::reacquired(&self.locked);
+        Some(MutexGuard {
+            locked: &self.locked,
+            data: self.data.get(),
+        })
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the [`Mutex`] mutably, and a mutable reference is guaranteed to be exclusive in Rust,
+    /// no actual locking needs to take place -- the mutable borrow statically guarantees no locks exist. As such,
+    /// this is a 'zero-cost' operation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut lock = nospin::Mutex::new(0);
+    /// *lock.get_mut() = 10;
+    /// assert_eq!(*lock.lock(), 10);
+    /// ```
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut T {
+        // We know statically that there are no other references to `self`, so
+        // there's no need to lock the inner mutex.
+        unsafe { &mut *self.data.get() }
+    }
+
+    /// Consumes a boxed [`Mutex`] and unwraps the underlying data into a [`Box`] of its own.
+    ///
+    /// Unlike [`Mutex::into_inner`], this works for unsized data such as trait objects or slices, moving the data
+    /// into a new allocation sized for it and freeing the allocation of the [`Mutex`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::fmt::Display;
+    /// use nospin::Mutex;
+    ///
+    /// let lock: Box<Mutex<dyn Display>> = Mutex::boxed(42);
+    /// let data: Box<dyn Display> = lock.into_inner_boxed();
+    /// assert_eq!(data.to_string(), "42");
+    /// ```
+    pub fn into_inner_boxed(self: Box<Self>) -> Box<T> {
+        let mutex_layout = Layout::for_value(&*self);
+        let mutex = Box::into_raw(self);
+        unsafe {
+            let data = UnsafeCell::raw_get(&raw const (*mutex).data);
+            let layout = Layout::for_value(&*data);
+
+            let new = if layout.size() == 0 {
+                core::ptr::without_provenance_mut(layout.align())
+            } else {
+                let new = alloc::alloc::alloc(layout);
+                if new.is_null() {
+                    alloc::alloc::handle_alloc_error(layout);
+                }
+                new
+            };
+            core::ptr::copy_nonoverlapping(data as *const u8, new, layout.size());
+
+            // Point the (possibly fat) data pointer at the new allocation, keeping its metadata
+            let mut boxed = data;
+            *(&mut boxed as *mut *mut T as *mut *mut u8) = new;
+
+            // The data has been moved out, so free the mutex without dropping it
+            if mutex_layout.size() != 0 {
+                alloc::alloc::dealloc(mutex as *mut u8, mutex_layout);
+            }
+            Box::from_raw(boxed)
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data through a shared reference, without locking.
+    ///
+    /// This is intended for FFI callbacks that receive a `&Mutex` while the caller knows that it holds the lock,
+    /// for example through a guard leaked with [`MutexGuard::leak`], but cannot prove it to the borrow checker.
+    ///
+    /// # Safety
+    ///
+    /// This is *extremely* unsafe if the lock is not held by the caller, or if any other reference to the data
+    /// (including one obtained from a guard) is live for as long as the returned reference is, as both would
+    /// violate Rust's aliasing rules.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let lock = nospin::Mutex::new(0);
+    /// let guard = lock.lock();
+    /// core::mem::forget(guard);
+    ///
+    /// // The lock is held, and the guard no longer refers to the data
+    /// unsafe {
+    ///     *lock.get_mut_unchecked() = 10;
+    ///     lock.force_unlock();
+    /// }
+    /// assert_eq!(*lock.lock(), 10);
+    /// ```
+    #[inline(always)]
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn get_mut_unchecked(&self) -> &mut T {
+        debug_check!(
+            self.is_locked(),
+            "Attempted to access the data of an unlocked Mutex without locking it"
+        );
+        unsafe { &mut *self.data.get() }
+    }
+
+    /// Returns `true` if the lock is currently held.
+    ///
+    /// # Safety
+    ///
+    /// This function provides no synchronization guarantees and so its result should be considered 'out of date'
+    /// the instant it is called. Do not use it for synchronization purposes. However, it may be useful as a heuristic.
+    #[inline(always)]
+    pub fn is_locked(&self) -> bool {
+        unsafe { *self.locked.get() }
+    }
+
+    /// Panics if the lock is currently held.
+    ///
+    /// This is intended for tests, to check that code released every guard it took without acquiring the lock
+    /// itself as `assert!(lock.try_lock().is_some())` would.
+    ///
+    /// ```should_panic
+    /// let lock = nospin::Mutex::new(0);
+    /// lock.assert_unlocked();
+    ///
+    /// let _guard = lock.lock();
+    /// lock.assert_unlocked();
+    /// ```
+    pub fn assert_unlocked(&self) {
+        assert!(!self.is_locked(), "Mutex is unexpectedly locked");
+    }
+
+    /// Locks the [`Mutex`] and returns a guard that permits access to the inner data.
+    ///
+    /// The returned value may be dereferenced for data access
+    /// and the lock will be dropped when the guard falls out of scope.
+    ///
+    /// ```
+    /// let lock = nospin::Mutex::new(0);
+    /// {
+    ///     let mut data = lock.lock();
+    ///     // The lock is now locked and the data can be accessed
+    ///     *data += 1;
+    ///     // The lock is implicitly dropped at the end of the scope
+    /// }
+    /// ```
+    #[inline(always)]
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        #[cfg(feature = "lock_order")]
+        lock_order::check(&self.lock_id);
+        if !self.try_mark_locked() {
+            panic!("Mutex is already locked");
+        }
+        MutexGuard {
+            locked: &self.locked,
+            data: self.data.get(),
+        }
+    }
+
+    /// Locks the [`Mutex`] and runs `f` with mutable access to the inner data, returning its result.
+    ///
+    /// The lock is released explicitly as soon as `f` returns, so this does not rely on any destructor
+    /// running on the success path. Should `f` unwind, a drop guard releases the lock instead. With
+    /// `panic = "abort"` nothing runs after a panic at all, so the lock is never observed as held
+    /// once `f` has completed.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the lock is already held.
+    ///
+    /// ```
+    /// let lock = nospin::Mutex::new(0);
+    ///
+    /// let result = lock.guard_free(|data| {
+    ///     *data += 1;
+    ///     *data
+    /// });
+    /// assert_eq!(result, 1);
+    /// assert!(!lock.is_locked());
+    /// ```
+    #[inline(always)]
+    pub fn guard_free<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+        struct Unlock<'a>(&'a UnsafeCell<bool>);
+
+        impl Drop for Unlock<'_> {
+            fn drop(&mut self) {
+                release(self.0);
+            }
+        }
+
+        #[cfg(feature = "lock_order")]
+        lock_order::check(&self.lock_id);
+        if !self.try_mark_locked() {
+            panic!("Mutex is already locked");
+        }
+        let unlock = Unlock(&self.locked);
+
+        let result = f(unsafe { &mut *self.data.get() });
+
+        // Only needed for unwinding, clear the flag directly on the success path
+        forget(unlock);
+        release(&self.locked);
+        result
+    }
+
+    /// Locks the [`Mutex`] and runs `f` with mutable access to the inner data, calling `on_poison` should `f` panic.
+    ///
+    /// Unlike `std::sync::Mutex`, this [`Mutex`] is never poisoned. This provides a hook to implement poisoning
+    /// around a critical section instead, for example by marking the protected resource as suspect. The lock is
+    /// released before `on_poison` is called, so the hook is free to lock the mutex itself. Unwinding then
+    /// continues as usual.
+    ///
+    /// As `on_poison` runs while unwinding, it panicking in turn aborts the process.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the lock is already held, or if `f` panics.
+    ///
+    /// ```
+    /// use nospin::Mutex;
+    ///
+    /// let lock = Mutex::new((0, false));
+    ///
+    /// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     lock.with_recover(
+    ///         |(value, _)| {
+    ///             *value += 1;
+    ///             panic!("interrupted halfway");
+    ///         },
+    ///         |lock| lock.lock().1 = true,
+    ///     )
+    /// }));
+    /// assert!(result.is_err());
+    /// assert_eq!(*lock.lock(), (1, true));
+    /// ```
+    #[inline(always)]
+    pub fn with_recover<R, F: FnOnce(&mut T) -> R>(&self, f: F, on_poison: fn(&Mutex<T>)) -> R {
+        struct Recover<'a, T: ?Sized>(&'a Mutex<T>, fn(&Mutex<T>));
+
+        impl<T: ?Sized> Drop for Recover<'_, T> {
+            fn drop(&mut self) {
+                release(&self.0.locked);
+                (self.1)(self.0);
+            }
+        }
+
+        #[cfg(feature = "lock_order")]
+        lock_order::check(&self.lock_id);
+        if !self.try_mark_locked() {
+            panic!("Mutex is already locked");
+        }
+        let recover = Recover(self, on_poison);
+
+        let result = f(unsafe { &mut *self.data.get() });
+
+        // Only needed for unwinding, clear the flag directly on the success path
+        forget(recover);
+        release(&self.locked);
+        result
+    }
+
+    /// Returns a mutable pointer to the underlying data.
+    ///
+    /// This is mostly meant to be used for applications which require manual unlocking, but where
+    /// storing both the lock and the pointer to the inner data gets inefficient.
+    #[cfg(feature = "spin_compat")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "spin_compat")))]
+    #[inline(always)]
+    pub fn as_mut_ptr(&self) -> *mut T {
+        self.data.get()
+    }
+
+    /// Try to lock this [`Mutex`], returning a lock guard if successful.
+    ///
+    /// Unlike [`Mutex::try_lock`], this function is allowed to spuriously fail even when locking would otherwise
+    /// succeed. It never actually does, and is provided for API compatibility with `spin`.
+    #[inline(always)]
+    pub fn try_lock_weak(&self) -> Option<MutexGuard<'_, T>> {
+        self.try_lock()
+    }
+
+    /// Try to lock this [`Mutex`], returning a lock guard if successful.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let lock = nospin::Mutex::new(42);
+    ///
+    /// let maybe_guard = lock.try_lock();
+    /// assert!(maybe_guard.is_some());
+    ///
+    /// // `maybe_guard` is still held, so the second call fails
+    /// let maybe_guard2 = lock.try_lock();
+    /// assert!(maybe_guard2.is_none());
+    /// ```
+    #[inline(always)]
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        if self.try_mark_locked() {
+            Some(MutexGuard {
+                locked: &self.locked,
+                data: self.data.get(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// An alias of [`Mutex`] under the name used by `spin`, for ease of porting.
+#[cfg(feature = "spin_compat")]
+#[cfg_attr(docsrs, doc(cfg(feature = "spin_compat")))]
+pub type SpinMutex<T> = Mutex<T>;
+
+/// An alias of [`MutexGuard`] under the name used by `spin`, for ease of porting.
+#[cfg(feature = "spin_compat")]
+#[cfg_attr(docsrs, doc(cfg(feature = "spin_compat")))]
+pub type SpinMutexGuard<'a, T> = MutexGuard<'a, T>;
+
+/// A generic guard that will protect some data access
+pub struct MutexGuard<'a, T: 'a + ?Sized> {
+    locked: &'a UnsafeCell<bool>,
+    data: *mut T,
+}
+
+impl<'a, T: ?Sized> MutexGuard<'a, T> {
+    /// Returns a reference to the locked data.
+    ///
+    /// This is equivalent to dereferencing the guard. It is an associated function that needs to be used as
+    /// `MutexGuard::get(...)`, so as not to conflict with a method of the same name on the locked data.
+    #[inline(always)]
+    pub fn get(this: &Self) -> &T {
+        unsafe { &*this.data }
+    }
+
+    /// Returns a mutable reference to the locked data.
+    ///
+    /// This is equivalent to mutably dereferencing the guard. It is an associated function that needs to be
+    /// used as `MutexGuard::get_mut(...)`, so as not to conflict with a method of the same name on the locked data.
+    ///
+    /// ```
+    /// use nospin::MutexGuard;
+    ///
+    /// let lock = nospin::Mutex::new(0);
+    ///
+    /// let mut guard = lock.lock();
+    /// *MutexGuard::get_mut(&mut guard) += 1;
+    /// assert_eq!(*MutexGuard::get(&guard), 1);
+    /// ```
+    #[inline(always)]
+    pub fn get_mut(this: &mut Self) -> &mut T {
+        unsafe { &mut *this.data }
+    }
+
+    /// Returns a non-null pointer to the locked data, such as for handing it to C.
+    ///
+    /// The pointer is only valid while the guard is alive. It is an associated function that needs to be used as
+    /// `MutexGuard::as_non_null(...)`, so as not to conflict with a method of the same name on the locked data.
+    ///
+    /// ```
+    /// use nospin::MutexGuard;
+    ///
+    /// let lock = nospin::Mutex::new(0);
+    ///
+    /// let guard = lock.lock();
+    /// let ptr = MutexGuard::as_non_null(&guard);
+    /// unsafe { *ptr.as_ptr() = 1 };
+    /// assert_eq!(*guard, 1);
+    /// ```
+    #[inline(always)]
+    pub fn as_non_null(this: &Self) -> NonNull<T> {
+        unsafe { NonNull::new_unchecked(this.data) }
+    }
+
+    /// Releases the lock held by this guard.
+    ///
+    /// This is equivalent to dropping the guard, but makes the intent explicit.
+    ///
+    /// ```
+    /// let lock = nospin::Mutex::new(0);
+    ///
+    /// let guard = lock.lock();
+    /// nospin::MutexGuard::unlock(guard);
+    /// assert!(!lock.is_locked());
+    /// ```
+    #[inline(always)]
+    pub fn unlock(this: Self) {
+        drop(this);
+    }
+
+    /// Releases the lock held by this guard and then runs `f`, returning its result.
+    ///
+    /// The lock is guaranteed to have been released before `f` is called.
+    ///
+    /// ```
+    /// let lock = nospin::Mutex::new(0);
+    ///
+    /// let guard = lock.lock();
+    /// let relocked = nospin::MutexGuard::unlock_and(guard, || *lock.lock() + 1);
+    /// assert_eq!(relocked, 1);
+    /// ```
+    #[inline(always)]
+    pub fn unlock_and<R, F: FnOnce() -> R>(this: Self, f: F) -> R {
+        drop(this);
+        f()
+    }
+
+    /// Takes the value out of the locked data, leaving `Default::default()` in its place.
+    ///
+    /// The lock remains held by the guard. This is an associated function that needs to be used as
+    /// `MutexGuard::take(...)`, so as not to conflict with a method of the same name on the locked data.
+    ///
+    /// ```
+    /// let lock = nospin::Mutex::new(Some(5));
+    ///
+    /// let mut guard = lock.lock();
+    /// assert_eq!(nospin::MutexGuard::take(&mut guard), Some(5));
+    /// assert_eq!(*guard, None);
+    /// ```
+    #[inline(always)]
+    pub fn take(this: &mut Self) -> T
+    where
+        T: Default,
+    {
+        core::mem::take(&mut **this)
+    }
+
+    /// Leaks the guard, yielding a mutable reference to the underlying data.
+    ///
+    /// Note that this function will permanently lock the original [`Mutex`]. See
+    /// [`MutexGuard::leak_with_token`] for a way to release it again later.
+    #[cfg(feature = "spin_compat")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "spin_compat")))]
+    #[inline(always)]
+    pub fn leak(this: Self) -> &'a mut T {
+        let data = this.data;
+        forget(this);
+        unsafe { &mut *data }
+    }
+
+    /// Leaks the guard, yielding a mutable reference to the underlying data along with an
+    /// [`UnlockToken`] that can later be used to release the lock.
+    ///
+    /// Unlike the guard, the token does not release the lock when dropped. The lock is only released by
+    /// handing the reference back to [`UnlockToken::unlock`], which guarantees it can no longer be used
+    /// once the lock is released. Dropping the token leaves the lock held forever.
+    ///
+    /// ```
+    /// let lock = nospin::Mutex::new(0);
+    ///
+    /// let (data, token) = nospin::MutexGuard::leak_with_token(lock.lock());
+    /// *data += 1;
+    /// assert!(lock.is_locked());
+    ///
+    /// token.unlock(data);
+    /// assert!(!lock.is_locked());
+    /// assert_eq!(*lock.lock(), 1);
+    /// ```
+    #[inline(always)]
+    pub fn leak_with_token(this: Self) -> (&'a mut T, UnlockToken<'a, T>) {
+        let locked = this.locked;
+        let data = this.data;
+        forget(this);
+        (
+            unsafe { &mut *data },
+            UnlockToken {
+                locked,
+                data,
+                _invariant: PhantomData,
+            },
+        )
+    }
+}
+
+/// A handle to a [`Mutex`] locked by [`MutexGuard::leak_with_token`] which can be used to release it.
+///
+/// Dropping the token does *not* release the lock, [`UnlockToken::unlock`] must be called explicitly.
+#[must_use = "dropping the token leaves the mutex locked forever"]
+pub struct UnlockToken<'a, T: 'a + ?Sized> {
+    locked: &'a UnsafeCell<bool>,
+    data: *mut T,
+    // Invariant in 'a, so that `unlock` borrows the leaked reference for its whole lifetime
+    _invariant: PhantomData<fn(&'a ()) -> &'a ()>,
+}
+
+impl<'a, T: ?Sized> UnlockToken<'a, T> {
+    /// Releases the lock this token was created for.
+    ///
+    /// `data` must be the reference returned alongside this token by [`MutexGuard::leak_with_token`], which
+    /// is consumed so that it cannot outlive the lock.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `data` does not point to the data protected by the locked [`Mutex`].
+    #[inline(always)]
+    pub fn unlock(self, data: &'a mut T) {
+        assert!(
+            core::ptr::eq(data, self.data),
+            "UnlockToken used with data from a different Mutex"
+        );
+        release(self.locked);
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for UnlockToken<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("UnlockToken")
+    }
+}
+
+impl<T: ?Sized> TryLock for Mutex<T> {
+    type Target = T;
+    type Guard<'a>
+        = MutexGuard<'a, T>
+    where
+        Self: 'a;
+
+    #[inline(always)]
+    fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        Mutex::try_lock(self)
+    }
+}
+
+impl<T: ?Sized> Guard for MutexGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn get(&self) -> &T {
+        MutexGuard::get(self)
+    }
+}
+
+impl<T: ?Sized> GuardMut for MutexGuard<'_, T> {
+    #[inline(always)]
+    fn get_mut(&mut self) -> &mut T {
+        MutexGuard::get_mut(self)
+    }
+}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.data }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for MutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if cfg!(feature = "verbose_debug") {
+            f.debug_tuple("MutexGuard").field(&&**self).finish()
+        } else {
+            fmt::Debug::fmt(&**self, f)
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for MutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> fmt::Pointer for MutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.data, f)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq<T> for MutexGuard<'_, T> {
+    fn eq(&self, other: &T) -> bool {
+        **self == *other
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for MutexGuard<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd<T> for MutexGuard<'_, T> {
+    fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(other)
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for MutexGuard<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+#[cfg(feature = "embedded_io")]
+impl<D: ?Sized + embedded_io::ErrorType> embedded_io::ErrorType for MutexGuard<'_, D> {
+    type Error = D::Error;
+}
+
+#[cfg(feature = "embedded_io")]
+impl<D: ?Sized + embedded_io::Read> embedded_io::Read for MutexGuard<'_, D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        (**self).read(buf)
+    }
+}
+
+#[cfg(feature = "embedded_io")]
+impl<D: ?Sized + embedded_io::Write> embedded_io::Write for MutexGuard<'_, D> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        (**self).write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        (**self).flush()
+    }
+}
+
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        debug_check!(
+            unsafe { *self.locked.get() },
+            "MutexGuard dropped while its Mutex was unlocked, was it force unlocked?"
+        );
+        release(self.locked);
+    }
+}
+
+/// A fixed number of independently lockable [`Mutex`]es, such as a table of per-channel locks.
+///
+/// Since a `[Mutex<T>; N]` cannot be built by repeating a non-`Copy` value, this provides the ways to construct
+/// one: [`MutexArray::INIT`] for unit data, [`MutexArray::from_array`] with an inline `const` block for any
+/// `const` constructible data, or [`MutexArray::from_fn`] at runtime.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(not(feature = "strict_send_sync"))]
+/// # fn main() {
+/// use nospin::{Mutex, mutex::MutexArray};
+///
+/// static CHANNELS: MutexArray<Vec<u8>, 4> = MutexArray::from_array([const { Mutex::new(Vec::new()) }; 4]);
+///
+/// CHANNELS.lock(2).push(42);
+/// assert!(CHANNELS.try_lock(2).is_some());
+/// assert_eq!(*CHANNELS.lock(2), [42]);
+/// # }
+/// # #[cfg(feature = "strict_send_sync")]
+/// # fn main() {}
+/// ```
+pub struct MutexArray<T, const N: usize> {
+    mutexes: [Mutex<T>; N],
+}
+
+impl<const N: usize> MutexArray<(), N> {
+    /// Initialization constant of [`MutexArray`], holding `N` unlocked mutexes of unit data.
+    #[allow(clippy::declare_interior_mutable_const)]
+    pub const INIT: Self = Self::from_array([Mutex::INIT; N]);
+}
+
+impl<T, const N: usize> MutexArray<T, N> {
+    /// Creates a new [`MutexArray`] from an array of mutexes.
+    #[inline(always)]
+    pub const fn from_array(mutexes: [Mutex<T>; N]) -> Self {
+        Self { mutexes }
+    }
+
+    /// Creates a new [`MutexArray`] whose mutex at each index wraps `f(index)`.
+    ///
+    /// ```
+    /// let ports = nospin::mutex::MutexArray::<_, 3>::from_fn(|i| i * 10);
+    /// assert_eq!(*ports.lock(2), 20);
+    /// ```
+    #[inline(always)]
+    pub fn from_fn<F: FnMut(usize) -> T>(mut f: F) -> Self {
+        Self::from_array(core::array::from_fn(|i| Mutex::new(f(i))))
+    }
+
+    /// Locks the mutex at `index`, returning a guard that permits access to its data.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `index` is out of bounds or if the mutex is already locked.
+    #[inline(always)]
+    pub fn lock(&self, index: usize) -> MutexGuard<'_, T> {
+        self.mutexes[index].lock()
+    }
+
+    /// Tries to lock the mutex at `index`, returning `None` if it is already locked.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `index` is out of bounds.
+    #[inline(always)]
+    pub fn try_lock(&self, index: usize) -> Option<MutexGuard<'_, T>> {
+        self.mutexes[index].try_lock()
+    }
+
+    /// Returns the mutex at `index`, or `None` if it is out of bounds.
+    #[inline(always)]
+    pub fn get(&self, index: usize) -> Option<&Mutex<T>> {
+        self.mutexes.get(index)
+    }
+
+    /// Returns the number of mutexes, `N`.
+    #[inline(always)]
+    #[allow(clippy::len_without_is_empty)]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Consumes this [`MutexArray`], returning the underlying mutexes.
+    #[inline(always)]
+    pub fn into_inner(self) -> [Mutex<T>; N] {
+        self.mutexes
+    }
+}
+
+impl<T: Default, const N: usize> Default for MutexArray<T, N> {
+    fn default() -> Self {
+        Self::from_fn(|_| T::default())
+    }
+}
+
+// Compares the lock flags rather than the mutexes themselves, as a mutex may share its address with another
+// mutex nested inside its data.
+fn same_lock<A: ?Sized, B: ?Sized>(a: &Mutex<A>, b: &Mutex<B>) -> bool {
+    core::ptr::eq(a.locked.get(), b.locked.get())
+}
+
+/// Locks two mutexes in order, returning both guards.
+///
+/// Should locking the second mutex panic, the first is released again while unwinding.
+///
+/// # Panics
+///
+/// This function will panic if the same mutex is passed twice, or if either mutex is already locked.
+///
+/// # Example
+///
+/// ```
+/// use nospin::{Mutex, mutex::lock2};
+///
+/// let names = Mutex::new(vec!["a"]);
+/// let count = Mutex::new(1);
+///
+/// let (mut names, mut count) = lock2(&names, &count);
+/// names.push("b");
+/// *count += 1;
+/// ```
+pub fn lock2<'a, A: ?Sized, B: ?Sized>(
+    a: &'a Mutex<A>,
+    b: &'a Mutex<B>,
+) -> (MutexGuard<'a, A>, MutexGuard<'a, B>) {
+    assert!(!same_lock(a, b), "the same Mutex was passed to lock2 twice");
+    let a = a.lock();
+    (a, b.lock())
+}
+
+/// Locks three mutexes in order, returning all three guards.
+///
+/// Should locking a later mutex panic, those already locked are released again while unwinding.
+///
+/// # Panics
+///
+/// This function will panic if the same mutex is passed more than once, or if any mutex is already locked.
+///
+/// # Example
+///
+/// ```should_panic
+/// use nospin::{Mutex, mutex::lock3};
+///
+/// let a = Mutex::new(1);
+/// let b = Mutex::new(2);
+///
+/// // Panics, re-locking `a` would be a re-entrancy bug
+/// let _ = lock3(&a, &b, &a);
+/// ```
+pub fn lock3<'a, A: ?Sized, B: ?Sized, C: ?Sized>(
+    a: &'a Mutex<A>,
+    b: &'a Mutex<B>,
+    c: &'a Mutex<C>,
+) -> (MutexGuard<'a, A>, MutexGuard<'a, B>, MutexGuard<'a, C>) {
+    assert!(
+        !same_lock(a, b) && !same_lock(a, c) && !same_lock(b, c),
+        "the same Mutex was passed to lock3 more than once"
+    );
+    let a = a.lock();
+    let b = b.lock();
+    (a, b, c.lock())
 }
 
+#[cfg(feature = "arc_lock")]
+#[cfg_attr(docsrs, doc(cfg(feature = "arc_lock")))]
 impl<T: ?Sized> Mutex<T> {
-    /// Force unlock this [`Mutex`].
+    /// Locks the [`Mutex`] through an [`Arc`], returning a guard that keeps the `Arc` alive and so is not
+    /// bound to the lifetime of a borrow.
     ///
-    /// # Safety
+    /// This allows a guard to be returned from a function or stored in a struct alongside the lock.
     ///
-    /// This is *extremely* unsafe if the lock is not held by the current
-    /// thread. However, this can be useful in some instances for exposing the
-    /// lock to FFI that doesn't know how to deal with RAII.
+    /// # Panics
+    ///
+    /// This function will panic if the lock is already held.
     #[inline(always)]
-    pub unsafe fn force_unlock(&self) {
-        unsafe { *self.locked.get() = false };
+    pub fn lock_owned(self: Arc<Self>) -> ArcMutexGuard<T> {
+        forget(self.lock());
+        ArcMutexGuard {
+            mutex: self,
+            _marker: PhantomData,
+        }
     }
 
-    /// Returns a mutable reference to the underlying data.
-    ///
-    /// Since this call borrows the [`Mutex`] mutably, and a mutable reference is guaranteed to be exclusive in Rust,
-    /// no actual locking needs to take place -- the mutable borrow statically guarantees no locks exist. As such,
-    /// this is a 'zero-cost' operation.
-    ///
-    /// # Example
+    /// Tries to lock the [`Mutex`] through an [`Arc`], returning an owned guard if it was not already held.
     ///
-    /// ```
-    /// let mut lock = nospin::Mutex::new(0);
-    /// *lock.get_mut() = 10;
-    /// assert_eq!(*lock.lock(), 10);
-    /// ```
+    /// See [`Mutex::lock_owned`].
     #[inline(always)]
-    pub fn get_mut(&mut self) -> &mut T {
-        // We know statically that there are no other references to `self`, so
-        // there's no need to lock the inner mutex.
-        unsafe { &mut *self.data.get() }
+    pub fn try_lock_owned(self: Arc<Self>) -> Option<ArcMutexGuard<T>> {
+        forget(self.try_lock()?);
+        Some(ArcMutexGuard {
+            mutex: self,
+            _marker: PhantomData,
+        })
     }
+}
 
-    /// Returns `true` if the lock is currently held.
-    ///
-    /// # Safety
+/// A guard returned by [`Mutex::lock_owned`] that holds an [`Arc`] of its [`Mutex`] rather than borrowing it.
+///
+/// When the guard falls out of scope it will release the lock, and then drop its `Arc`.
+#[cfg(feature = "arc_lock")]
+#[cfg_attr(docsrs, doc(cfg(feature = "arc_lock")))]
+pub struct ArcMutexGuard<T: ?Sized> {
+    mutex: Arc<Mutex<T>>,
+    // Neither `Send` nor `Sync`, like `MutexGuard`: the `Arc` alone would allow sharing a `T` that is not `Sync`
+    _marker: PhantomData<*const ()>,
+}
+
+#[cfg(feature = "arc_lock")]
+impl<T: ?Sized> ArcMutexGuard<T> {
+    /// Returns the [`Arc`] of the [`Mutex`] this guard holds.
     ///
-    /// This function provides no synchronization guarantees and so its result should be considered 'out of date'
-    /// the instant it is called. Do not use it for synchronization purposes. However, it may be useful as a heuristic.
+    /// This is an associated function that needs to be used as `ArcMutexGuard::mutex(...)`, so as not to
+    /// conflict with a method of the same name on the locked data.
     #[inline(always)]
-    pub fn is_locked(&self) -> bool {
-        unsafe { *self.locked.get() }
+    pub fn mutex(this: &Self) -> &Arc<Mutex<T>> {
+        &this.mutex
     }
+}
+
+#[cfg(feature = "arc_lock")]
+impl<T: ?Sized> Deref for ArcMutexGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+#[cfg(feature = "arc_lock")]
+impl<T: ?Sized> DerefMut for ArcMutexGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+#[cfg(feature = "arc_lock")]
+impl<T: ?Sized> Guard for ArcMutexGuard<T> {
+    type Target = T;
 
-    /// Locks the [`Mutex`] and returns a guard that permits access to the inner data.
-    ///
-    /// The returned value may be dereferenced for data access
-    /// and the lock will be dropped when the guard falls out of scope.
-    ///
-    /// ```
-    /// let lock = nospin::Mutex::new(0);
-    /// {
-    ///     let mut data = lock.lock();
-    ///     // The lock is now locked and the data can be accessed
-    ///     *data += 1;
-    ///     // The lock is implicitly dropped at the end of the scope
-    /// }
-    /// ```
     #[inline(always)]
-    pub fn lock(&self) -> MutexGuard<T> {
-        if self.is_locked() {
-            panic!("Mutex is already locked");
-        }
-        unsafe {
-            *self.locked.get() = true;
-        }
-        MutexGuard {
-            locked: self.locked.get(),
-            data: self.data.get(),
+    fn get(&self) -> &T {
+        self
+    }
+}
+
+#[cfg(feature = "arc_lock")]
+impl<T: ?Sized> GuardMut for ArcMutexGuard<T> {
+    #[inline(always)]
+    fn get_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+#[cfg(feature = "arc_lock")]
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ArcMutexGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if cfg!(feature = "verbose_debug") {
+            f.debug_tuple("ArcMutexGuard").field(&&**self).finish()
+        } else {
+            fmt::Debug::fmt(&**self, f)
         }
     }
+}
 
-    /// Try to lock this [`Mutex`], returning a lock guard if successful.
-    ///
-    /// # Example
+#[cfg(feature = "arc_lock")]
+impl<T: ?Sized + fmt::Display> fmt::Display for ArcMutexGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "branded")]
+#[cfg_attr(docsrs, doc(cfg(feature = "branded")))]
+impl<T: ?Sized> Mutex<T> {
+    /// Locks the [`Mutex`] for the duration of `f`, passing it a guard branded with a unique lifetime.
     ///
-    /// ```
-    /// let lock = nospin::Mutex::new(42);
+    /// As `f` must accept a guard of any lifetime `'id`, the guard cannot be returned from `f`, nor stored
+    /// anywhere that outlives the call: no type outside of `f` can name `'id`. The lock is therefore held for
+    /// exactly the duration of `f`, which is a stronger guarantee than the guard being `!Send`.
     ///
-    /// let maybe_guard = lock.try_lock();
-    /// assert!(maybe_guard.is_some());
+    /// # Panics
     ///
-    /// // `maybe_guard` is still held, so the second call fails
-    /// let maybe_guard2 = lock.try_lock();
-    /// assert!(maybe_guard2.is_none());
-    /// ```
+    /// This function will panic if the lock is already held.
     #[inline(always)]
-    pub fn try_lock(&self) -> Option<MutexGuard<T>> {
-        if self.is_locked() {
-            None
-        } else {
-            unsafe {
-                *self.locked.get() = true;
-            }
-            Some(MutexGuard {
-                locked: self.locked.get(),
-                data: self.data.get(),
-            })
-        }
+    pub fn scope<R>(&self, f: impl for<'id> FnOnce(BrandedGuard<'id, T>) -> R) -> R {
+        f(BrandedGuard {
+            guard: self.lock(),
+            brand: PhantomData,
+        })
     }
 }
 
-/// A generic guard that will protect some data access
-pub struct MutexGuard<T: ?Sized> {
-    locked: *mut bool,
-    data: *mut T,
+/// A guard passed to the closure of [`Mutex::scope`], which cannot escape the closure.
+///
+/// The `'id` lifetime is invariant, so it can be neither shortened nor extended to match a lifetime outside
+/// of the closure. When the guard falls out of scope it will release the lock.
+#[cfg(feature = "branded")]
+#[cfg_attr(docsrs, doc(cfg(feature = "branded")))]
+pub struct BrandedGuard<'id, T: ?Sized> {
+    guard: MutexGuard<'id, T>,
+    brand: PhantomData<fn(&'id ()) -> &'id ()>,
 }
 
-impl<T: ?Sized> Deref for MutexGuard<T> {
+#[cfg(feature = "branded")]
+impl<T: ?Sized> Deref for BrandedGuard<'_, T> {
     type Target = T;
 
     fn deref(&self) -> &T {
-        unsafe { &*self.data }
+        &self.guard
     }
 }
 
-impl<T: ?Sized> DerefMut for MutexGuard<T> {
+#[cfg(feature = "branded")]
+impl<T: ?Sized> DerefMut for BrandedGuard<'_, T> {
     fn deref_mut(&mut self) -> &mut T {
-        unsafe { &mut *self.data }
+        &mut self.guard
+    }
+}
+
+#[cfg(feature = "branded")]
+impl<T: ?Sized> Guard for BrandedGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn get(&self) -> &T {
+        self
+    }
+}
+
+#[cfg(feature = "branded")]
+impl<T: ?Sized> GuardMut for BrandedGuard<'_, T> {
+    #[inline(always)]
+    fn get_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+#[cfg(feature = "branded")]
+impl<T: ?Sized + fmt::Debug> fmt::Debug for BrandedGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if cfg!(feature = "verbose_debug") {
+            f.debug_tuple("BrandedGuard").field(&&**self).finish()
+        } else {
+            fmt::Debug::fmt(&**self, f)
+        }
+    }
+}
+
+#[cfg(feature = "branded")]
+impl<T: ?Sized + fmt::Display> fmt::Display for BrandedGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
     }
 }
 
-impl<T: ?Sized> Drop for MutexGuard<T> {
+#[cfg(feature = "arc_lock")]
+impl<T: ?Sized> Drop for ArcMutexGuard<T> {
     fn drop(&mut self) {
-        unsafe { *self.locked = false }
+        debug_check!(
+            self.mutex.is_locked(),
+            "ArcMutexGuard dropped while its Mutex was unlocked, was it force unlocked?"
+        );
+        release(&self.mutex.locked);
     }
 }
 
+/// Declares a `static` [`Mutex`] around an unsized type, such as a trait object.
+///
+/// A `static` must have a sized type, so `static LOGGER: Mutex<dyn Logger>` cannot be written directly.
+/// This macro instead declares a hidden `static` holding the concretely typed [`Mutex`] and exposes it
+/// through a `&'static Mutex<dyn Logger>`, performing the unsizing coercion in the initializer. Since
+/// the reference derefs to the lock, the declared static can be used as if it were the [`Mutex`] itself.
+///
+/// The supported shape is a sized value coerced to an unsized type (typically `dyn Trait`, which
+/// must be `Send` for the static to be `Sync`):
+///
+/// ```
+/// # #[cfg(not(feature = "strict_send_sync"))]
+/// # fn main() {
+/// trait Logger {
+///     fn log(&mut self, msg: &str);
+/// }
+///
+/// struct Collect(Vec<String>);
+///
+/// impl Logger for Collect {
+///     fn log(&mut self, msg: &str) {
+///         self.0.push(msg.to_string());
+///     }
+/// }
+///
+/// nospin::static_mutex! {
+///     /// The global logger.
+///     pub static LOGGER: Mutex<dyn Logger + Send> = Mutex::<Collect>::new(Collect(Vec::new()));
+/// }
+///
+/// LOGGER.lock().log("hello");
+/// # }
+/// # #[cfg(feature = "strict_send_sync")]
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! static_mutex {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: Mutex<$ty:ty> = Mutex::<$concrete:ty>::new($init:expr);) => {
+        $(#[$attr])*
+        $vis static $name: &'static $crate::Mutex<$ty> = {
+            static INNER: $crate::Mutex<$concrete> = $crate::Mutex::new($init);
+            &INNER
+        };
+    };
+}
+
 #[cfg(feature = "lock_api")]
 unsafe impl lock_api_crate::RawMutex for Mutex<()> {
     type GuardMarker = lock_api_crate::GuardSend;
@@ -250,13 +1494,31 @@ unsafe impl lock_api_crate::RawMutex for Mutex<()> {
     }
 }
 
+#[cfg(feature = "lock_api")]
+#[cfg_attr(docsrs, doc(cfg(feature = "lock_api")))]
+impl<T> Mutex<T> {
+    /// Consumes this [`Mutex`], moving its data into a new, unlocked [`lock_api::Mutex`](crate::lock_api::Mutex).
+    #[inline(always)]
+    pub fn into_lock_api(self) -> crate::lock_api::Mutex<T> {
+        crate::lock_api::Mutex::new(self.into_inner())
+    }
+
+    /// Consumes a [`lock_api::Mutex`](crate::lock_api::Mutex), moving its data into a new, unlocked [`Mutex`].
+    #[inline(always)]
+    pub fn from_lock_api(mutex: crate::lock_api::Mutex<T>) -> Self {
+        Mutex::new(mutex.into_inner())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::prelude::v1::*;
 
     use std::sync::Arc;
     use std::sync::atomic::{AtomicUsize, Ordering};
+    #[cfg(not(feature = "strict_send_sync"))]
     use std::sync::mpsc::channel;
+    #[cfg(not(feature = "strict_send_sync"))]
     use std::thread;
 
     type Mutex<T> = super::Mutex<T>;
@@ -271,6 +1533,7 @@ mod tests {
         drop(m.lock());
     }
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn lots_and_lots() {
         static M: Mutex<()> = Mutex::<_>::new(());
@@ -318,6 +1581,15 @@ mod tests {
         assert_eq!(m.into_inner(), NonCopy(10));
     }
 
+    #[test]
+    fn test_reset() {
+        let mut m = Mutex::<_>::new(NonCopy(10));
+        core::mem::forget(m.lock());
+        assert_eq!(m.reset(NonCopy(20)), NonCopy(10));
+        assert!(!m.is_locked());
+        assert_eq!(*m.lock(), NonCopy(20));
+    }
+
     #[test]
     fn test_into_inner_drop() {
         struct Foo(Arc<AtomicUsize>);
@@ -336,6 +1608,7 @@ mod tests {
         assert_eq!(num_drops.load(Ordering::SeqCst), 1);
     }
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn test_mutex_arc_nested() {
         // Tests nested mutexes and access
@@ -353,6 +1626,7 @@ mod tests {
         t.join().unwrap();
     }
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn test_mutex_arc_access_in_unwind() {
         let arc = Arc::new(Mutex::<_>::new(1));
@@ -386,6 +1660,269 @@ mod tests {
         assert_eq!(&*mutex.lock(), comp);
     }
 
+    #[test]
+    fn test_into_inner_boxed() {
+        let mutex: Box<Mutex<[u64]>> = Box::new(Mutex::new([1, 2, 3]));
+        *mutex.lock().last_mut().unwrap() = 4;
+        assert_eq!(*mutex.into_inner_boxed(), [1, 2, 4]);
+
+        let mutex: Box<Mutex<[u8]>> = Box::new(Mutex::new([]));
+        assert!(mutex.into_inner_boxed().is_empty());
+
+        // The data is moved rather than dropped along with the mutex
+        struct Foo(Arc<AtomicUsize>);
+        impl Drop for Foo {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        let num_drops = Arc::new(AtomicUsize::new(0));
+        let mutex: Box<Mutex<dyn Send>> = Mutex::boxed(Foo(num_drops.clone()));
+        let data = mutex.into_inner_boxed();
+        assert_eq!(num_drops.load(Ordering::SeqCst), 0);
+        drop(data);
+        assert_eq!(num_drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(not(feature = "strict_send_sync"))]
+    #[test]
+    fn init_constant() {
+        static M: [Mutex<()>; 4] = [Mutex::INIT; 4];
+        let _g = M[0].lock();
+        assert!(M[0].is_locked());
+        assert!(M[1].try_lock().is_some());
+    }
+
+    #[test]
+    fn test_guard_cmp() {
+        let a = Mutex::<_>::new(5);
+        let b = Mutex::<_>::new(7);
+        let (ga, gb) = (a.lock(), b.lock());
+        assert_eq!(ga, 5);
+        assert_ne!(ga, gb);
+        assert!(ga < 6);
+        assert!(ga < gb);
+    }
+
+    #[cfg(not(feature = "lock_order"))]
+    #[test]
+    fn test_from_ptr_checked() {
+        use core::mem::{align_of, size_of};
+
+        assert_eq!(size_of::<Mutex<u32>>(), 8);
+        assert_eq!(align_of::<Mutex<u64>>(), align_of::<u64>());
+
+        // A buffer laid out as a C `struct { bool locked; uint32_t data; }`
+        let buffer: &'static mut [u32; 3] = Box::leak(Box::new([0, 42, 0]));
+        let ptr = buffer.as_mut_ptr();
+        unsafe {
+            let lock = Mutex::from_ptr_checked(ptr).unwrap();
+            *lock.lock() += 1;
+            assert_eq!(ptr.add(1).read(), 43);
+
+            ptr.cast::<bool>().write(true);
+            assert!(lock.try_lock().is_none());
+
+            assert!(Mutex::<u32>::from_ptr_checked(core::ptr::null_mut()).is_none());
+            assert!(Mutex::from_ptr_checked(ptr.cast::<u8>().add(2).cast::<u32>()).is_none());
+        }
+    }
+
+    #[cfg(feature = "spin_compat")]
+    #[test]
+    fn test_spin_compat() {
+        let lock: super::SpinMutex<i32> = Mutex::new(1);
+        let guard: super::SpinMutexGuard<'_, i32> = lock.try_lock_weak().unwrap();
+        assert!(lock.try_lock_weak().is_none());
+        let data = super::MutexGuard::leak(guard);
+        *data += 1;
+        assert!(lock.is_locked());
+        assert_eq!(unsafe { lock.as_mut_ptr().read() }, 2);
+    }
+
+    #[test]
+    fn test_guard_free() {
+        let m = Mutex::<_>::new(1);
+        let r = m.guard_free(|x| {
+            *x += 1;
+            *x
+        });
+        assert_eq!(r, 2);
+        assert!(!m.is_locked());
+        assert_eq!(*m.lock(), 2);
+    }
+
+    // There is no `panic = "abort"` counterpart: the test harness needs unwinding on stable (`-Z panic-abort-tests`
+    // is nightly only), and with aborting a panic in `f` ends the process, leaving only the success path that
+    // `test_guard_free` already covers under either strategy.
+    #[cfg(panic = "unwind")]
+    #[test]
+    fn test_guard_free_unwind() {
+        let m = Mutex::<_>::new(1);
+        let r = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            m.guard_free(|x| {
+                *x += 1;
+                panic!();
+            })
+        }));
+        assert!(r.is_err());
+        assert!(!m.is_locked());
+        assert_eq!(*m.lock(), 2);
+    }
+
+    #[test]
+    fn test_extend_from_iter() {
+        let mut m: Mutex<Vec<i32>> = (0..3).collect();
+        m.extend(3..5);
+        assert!(!m.is_locked());
+        assert_eq!(*m.lock(), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let m = Mutex::new(vec![1, 2, 3]);
+        let mut sum = 0;
+        for x in m {
+            sum += x;
+        }
+        assert_eq!(sum, 6);
+    }
+
+    #[test]
+    fn test_leak_with_token() {
+        let m = Mutex::<_>::new(vec![1]);
+        let (data, token) = super::MutexGuard::leak_with_token(m.lock());
+        data.push(2);
+        assert!(m.try_lock().is_none());
+        token.unlock(data);
+        assert!(!m.is_locked());
+        assert_eq!(*m.lock(), [1, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_leak_with_token_wrong_data() {
+        let (a, b) = (Mutex::<_>::new(1), Mutex::<_>::new(2));
+        let (_, token) = super::MutexGuard::leak_with_token(a.lock());
+        let (data, _) = super::MutexGuard::leak_with_token(b.lock());
+        token.unlock(data);
+    }
+
+    #[test]
+    fn test_guard_debug() {
+        let m = Mutex::<_>::new(5);
+        let expected = if cfg!(feature = "verbose_debug") {
+            "MutexGuard(5)"
+        } else {
+            "5"
+        };
+        assert_eq!(format!("{:?}", m.lock()), expected);
+    }
+
+    #[test]
+    fn test_pointer() {
+        let m = Mutex::<_>::new(0);
+        let addr = format!("{:p}", m.data.get());
+        assert_eq!(format!("{:p}", m), addr);
+        assert_eq!(format!("{:p}", m.lock()), addr);
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn test_ord() {
+        use std::collections::BTreeSet;
+
+        let set: BTreeSet<_> = [3, 1, 2].into_iter().map(Mutex::new).collect();
+        let values: Vec<_> = set.iter().map(|m| *m.lock()).collect();
+        assert_eq!(values, [1, 2, 3]);
+
+        let m = Mutex::new(1);
+        assert_eq!(m, m);
+        assert!(!m.is_locked());
+    }
+
+    #[test]
+    #[should_panic(expected = "Mutex is already locked")]
+    fn test_ord_locked() {
+        let a = Mutex::new(1);
+        let _guard = a.lock();
+        let _ = a < Mutex::new(2);
+    }
+
+    #[cfg(any(debug_assertions, feature = "debug_checks"))]
+    #[test]
+    #[should_panic(expected = "was it force unlocked?")]
+    fn test_guard_drop_after_force_unlock() {
+        let lock = Mutex::new(());
+        let guard = lock.lock();
+        unsafe { lock.force_unlock() };
+        drop(guard);
+    }
+
+    #[test]
+    fn test_lock_all() {
+        use super::{lock2, lock3};
+
+        // A mutex nested at the start of another's data is not mistaken for it
+        let outer = Mutex::new(Mutex::new(1));
+        let (o, i) = lock2(&outer, unsafe { &*outer.data.get() });
+        assert!(o.is_locked());
+        assert_eq!(*i, 1);
+        drop((o, i));
+
+        let a = Mutex::new(0);
+        let b = Mutex::new(0);
+        let c = Mutex::new(0);
+        let _held = c.lock();
+        assert!(
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| lock3(&a, &b, &c))).is_err()
+        );
+        // Unwinding released the mutexes which had already been locked
+        assert!(!a.is_locked() && !b.is_locked());
+    }
+
+    #[test]
+    #[should_panic(expected = "the same Mutex was passed to lock2 twice")]
+    fn test_lock2_same() {
+        let a = Mutex::new(0);
+        let _ = super::lock2(&a, &a);
+    }
+
+    #[test]
+    fn test_mutex_array() {
+        use super::MutexArray;
+
+        let locks = MutexArray::<(), 3>::INIT;
+        let _held = locks.lock(1);
+        assert!(locks.try_lock(0).is_some());
+        assert!(locks.try_lock(1).is_none());
+        assert!(locks.get(3).is_none());
+        assert_eq!(locks.len(), 3);
+
+        let counts = MutexArray::<u32, 2>::default();
+        *counts.lock(1) += 1;
+        let [a, b] = counts.into_inner();
+        assert_eq!((a.into_inner(), b.into_inner()), (0, 1));
+    }
+
+    #[cfg(feature = "embedded_io")]
+    #[test]
+    fn test_embedded_io() {
+        use embedded_io::{Read, Write};
+
+        let mut buf = [0u8; 4];
+        let device = Mutex::new(&mut buf[..]);
+        device.lock().write_all(b"ab").unwrap();
+        assert_eq!(device.lock().len(), 2);
+        assert_eq!(&buf, b"ab\0\0");
+
+        let device = Mutex::new(&b"xyz"[..]);
+        let mut out = [0u8; 2];
+        device.lock().read_exact(&mut out).unwrap();
+        assert_eq!(&out, b"xy");
+        assert_eq!(*device.lock(), b"z");
+    }
+
     #[test]
     fn test_mutex_force_lock() {
         let lock = Mutex::<_>::new(());
@@ -395,4 +1932,54 @@ mod tests {
         }
         assert!(lock.try_lock().is_some());
     }
+
+    #[cfg(feature = "arc_lock")]
+    #[test]
+    #[cfg_attr(feature = "strict_send_sync", allow(clippy::arc_with_non_send_sync))]
+    fn test_lock_owned() {
+        use super::ArcMutexGuard;
+
+        fn acquire(lock: &Arc<Mutex<u32>>) -> ArcMutexGuard<u32> {
+            lock.clone().lock_owned()
+        }
+
+        let lock = Arc::new(Mutex::new(0));
+        let mut guard = acquire(&lock);
+        *guard += 1;
+        assert!(Arc::ptr_eq(ArcMutexGuard::mutex(&guard), &lock));
+        assert!(lock.clone().try_lock_owned().is_none());
+        assert_eq!(Arc::strong_count(&lock), 2);
+
+        drop(guard);
+        assert_eq!(Arc::strong_count(&lock), 1);
+        assert_eq!(*lock.clone().try_lock_owned().unwrap(), 1);
+        assert!(!lock.is_locked());
+    }
+
+    #[cfg(feature = "lock_api")]
+    #[test]
+    fn test_lock_api_conversion() {
+        let lock = Mutex::new(vec![1]);
+        lock.lock().push(2);
+
+        let lock = lock.into_lock_api();
+        lock.lock().push(3);
+
+        let lock = Mutex::from_lock_api(lock);
+        assert!(!lock.is_locked());
+        assert_eq!(lock.into_inner(), [1, 2, 3]);
+    }
+
+    #[cfg(feature = "branded")]
+    #[test]
+    fn test_scope() {
+        let lock = Mutex::new(1);
+        let doubled = lock.scope(|mut guard| {
+            *guard += 1;
+            assert!(lock.is_locked());
+            *guard * 2
+        });
+        assert_eq!(doubled, 4);
+        assert!(!lock.is_locked());
+    }
 }
@@ -1,9 +1,17 @@
 //! Synchronization primitives for one-time evaluation.
 use {
     alloc::fmt,
-    core::{cell::UnsafeCell, convert::Infallible, mem::MaybeUninit},
+    core::{
+        cell::UnsafeCell,
+        convert::Infallible,
+        hash::{Hash, Hasher},
+        mem::{MaybeUninit, forget},
+    },
 };
 
+#[cfg(feature = "async")]
+use core::task::{Context, Poll, Waker};
+
 /// A primitive that provides lazy one-time initialization.
 ///
 /// Unlike its `std::sync` equivalent, this is generalized such that the closure returns a
@@ -15,6 +23,8 @@ use {
 /// # Examples
 ///
 /// ```
+/// # #[cfg(not(feature = "strict_send_sync"))]
+/// # fn main() {
 /// use nospin;
 ///
 /// static START: nospin::Once = nospin::Once::new();
@@ -22,13 +32,60 @@ use {
 /// START.call_once(|| {
 ///     // run initialization here
 /// });
+/// # }
+/// # #[cfg(feature = "strict_send_sync")]
+/// # fn main() {}
 /// ```
 pub struct Once<T = ()> {
-    initialized: UnsafeCell<bool>,
-    panicked: UnsafeCell<bool>,
+    status: UnsafeCell<Status>,
+    #[cfg(feature = "debug_counters")]
+    init_count: UnsafeCell<usize>,
+    #[cfg(feature = "async")]
+    waker: UnsafeCell<Option<Waker>>,
     data: UnsafeCell<MaybeUninit<T>>,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Incomplete,
+    Running,
+    Complete,
+    Panicked,
+}
+
+// Poisons the `Once` should the initialization closure panic
+struct Poison<'a>(&'a UnsafeCell<Status>);
+
+impl Drop for Poison<'_> {
+    fn drop(&mut self) {
+        unsafe { *self.0.get() = Status::Panicked }
+    }
+}
+
+/// The error returned by [`Once::get_or_init_checked`] when a previous initialization closure panicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Poisoned;
+
+impl fmt::Display for Poisoned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Once instance has previously been poisoned")
+    }
+}
+
+/// State passed to the initialization closure of [`Once::try_call_once_force`].
+#[derive(Debug)]
+pub struct OnceState {
+    poisoned: bool,
+}
+
+impl OnceState {
+    /// Returns `true` if the [`Once`] was poisoned by a panicking initialization closure before this one was
+    /// called, meaning the closure is recovering from that failure.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+}
+
 impl<T: fmt::Debug> fmt::Debug for Once<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut d = f.debug_tuple("Once");
@@ -41,12 +98,68 @@ impl<T: fmt::Debug> fmt::Debug for Once<T> {
     }
 }
 
+/// Compares the values of two [`Once`]s.
+///
+/// An uninitialized [`Once`] counts as a distinct state: it is equal to other uninitialized [`Once`]s,
+/// but never to an initialized one. A poisoned [`Once`] is treated as uninitialized.
+///
+/// ```
+/// use nospin::Once;
+///
+/// assert_eq!(Once::<u32>::new(), Once::new());
+/// assert_eq!(Once::initialized(1), Once::initialized(1));
+/// assert_ne!(Once::initialized(1), Once::new());
+/// ```
+impl<T: PartialEq> PartialEq for Once<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Eq> Eq for Once<T> {}
+
+/// Hashes whether the [`Once`] has been initialized, followed by the value if it has, consistently
+/// with its `PartialEq` implementation.
+impl<T: Hash> Hash for Once<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get().hash(state)
+    }
+}
+
 impl<T> From<T> for Once<T> {
     fn from(data: T) -> Self {
         Self::initialized(data)
     }
 }
 
+/// Creates an initialized [`Once`] from `Some`, or an uninitialized one from `None`.
+///
+/// Because `Once<Option<T>>` can also be built from an `Option<T>` via `From<T>`, the target type
+/// usually needs to be spelled out.
+///
+/// ```
+/// use nospin::Once;
+///
+/// let once: Once<u32> = Some(1).into();
+/// assert_eq!(once.get(), Some(&1));
+/// assert_eq!(Option::<u32>::from(once), Some(1));
+/// ```
+impl<T> From<Option<T>> for Once<T> {
+    fn from(data: Option<T>) -> Self {
+        match data {
+            Some(data) => Self::initialized(data),
+            None => Self::new(),
+        }
+    }
+}
+
+impl<T> From<Once<T>> for Option<T> {
+    fn from(once: Once<T>) -> Self {
+        once.is_completed()
+            .then(|| unsafe { once.force_into_inner() })
+    }
+}
+
 impl<T> Drop for Once<T> {
     fn drop(&mut self) {
         // No need to do any atomic access here, we have &mut!
@@ -59,15 +172,20 @@ impl<T> Drop for Once<T> {
     }
 }
 
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: Send + Sync> Sync for Once<T> {}
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: Send> Send for Once<T> {}
 
 impl<T> Once<T> {
     /// Initialization constant of [`Once`].
     #[allow(clippy::declare_interior_mutable_const)]
     pub const INIT: Self = Self {
-        initialized: UnsafeCell::new(false),
-        panicked: UnsafeCell::new(false),
+        status: UnsafeCell::new(Status::Incomplete),
+        #[cfg(feature = "debug_counters")]
+        init_count: UnsafeCell::new(0),
+        #[cfg(feature = "async")]
+        waker: UnsafeCell::new(None),
         data: UnsafeCell::new(MaybeUninit::uninit()),
     };
 
@@ -103,12 +221,16 @@ impl<T> Once<T> {
         unsafe { &mut *(*self.data.get()).as_mut_ptr() }
     }
 
-    /// Get a reference to the initialized instance. Must only be called once COMPLETE.
+    /// Take the initialized instance out of the [`Once`]. Must only be called once COMPLETE.
     unsafe fn force_into_inner(self) -> T {
         // SAFETY:
         // * `UnsafeCell`/inner deref: data never changes again
         // * `MaybeUninit`/outer deref: data was initialized
-        unsafe { (*self.data.get()).as_ptr().read() }
+        // * The value is marked as moved out, so dropping `self` will not drop it a second time
+        unsafe {
+            *self.status.get() = Status::Incomplete;
+            (*self.data.get()).as_ptr().read()
+        }
     }
 
     /// Performs an initialization routine once and only once. The given closure
@@ -126,11 +248,20 @@ impl<T> Once<T> {
     ///
     /// This function will panic if the [`Once`] previously panicked while attempting
     /// to initialize. This is similar to the poisoning behaviour of `std::sync`'s
-    /// primitives.
+    /// primitives. It will also panic if called re-entrantly from within the
+    /// initialization closure of the same [`Once`].
+    ///
+    /// # Panic safety
+    ///
+    /// The value is only written to the [`Once`] after the closure has returned. If the
+    /// closure panics, nothing is stored and the [`Once`] is poisoned. Anything the closure
+    /// had already constructed is owned by the closure and dropped as usual while unwinding.
     ///
     /// # Examples
     ///
     /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
     /// use nospin;
     ///
     /// static INIT: nospin::Once<usize> = nospin::Once::new();
@@ -143,6 +274,9 @@ impl<T> Once<T> {
     ///     // ...
     /// # 2
     /// }
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
     /// ```
     pub fn call_once<F: FnOnce() -> T>(&self, f: F) -> &T {
         match self.try_call_once(|| Ok::<T, Infallible>(f())) {
@@ -151,6 +285,184 @@ impl<T> Once<T> {
         }
     }
 
+    /// Gets the contents of the [`Once`], initializing it with `f` if it has not been initialized yet.
+    ///
+    /// This is equivalent to [`Once::call_once`], and is provided for consistency with `OnceCell`.
+    /// The same panic safety guarantees apply.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the [`Once`] previously panicked while attempting
+    /// to initialize, or if called re-entrantly from within the initialization closure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let once = nospin::Once::new();
+    ///
+    /// assert_eq!(*once.get_or_init(|| 1), 1);
+    /// assert_eq!(*once.get_or_init(|| 2), 1);
+    /// ```
+    pub fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+        self.call_once(f)
+    }
+
+    /// Gets the mutable contents of the [`Once`], initializing it with `f` if it has not been initialized yet.
+    ///
+    /// Because this method requires a mutable reference to the [`Once`], it cannot be re-entered from `f`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the [`Once`] previously panicked while attempting
+    /// to initialize.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut once = nospin::Once::new();
+    ///
+    /// once.get_mut_or_init(Vec::new).push(1);
+    /// once.get_mut_or_init(Vec::new).push(2);
+    /// assert_eq!(once.get(), Some(&vec![1, 2]));
+    /// ```
+    pub fn get_mut_or_init<F: FnOnce() -> T>(&mut self, f: F) -> &mut T {
+        self.call_once(f);
+        unsafe { self.force_get_mut() }
+    }
+
+    /// Gets the contents of the [`Once`], initializing it by passing `arg` to `f` if it has not been
+    /// initialized yet.
+    ///
+    /// This is equivalent to [`Once::get_or_init`], but lets the initializer borrow its input rather
+    /// than capture it, avoiding a clone of large inputs that are only needed to build the value.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the [`Once`] previously panicked while attempting
+    /// to initialize, or if called re-entrantly from within the initialization closure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// static TABLE: nospin::Once<usize> = nospin::Once::new();
+    ///
+    /// let words = vec!["a", "bb", "ccc"];
+    ///
+    /// assert_eq!(*TABLE.get_or_init_with(&words, |w| w.iter().map(|s| s.len()).sum()), 6);
+    /// assert_eq!(words.len(), 3);
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    pub fn get_or_init_with<A: ?Sized, F: FnOnce(&A) -> T>(&self, arg: &A, f: F) -> &T {
+        match self.try_call_once(|| Ok::<T, Infallible>(f(arg))) {
+            Ok(x) => x,
+            Err(void) => match void {},
+        }
+    }
+
+    /// Gets the contents of the [`Once`], initializing it with `f` if it has not been initialized yet, or
+    /// returns [`Poisoned`] if a previous initialization closure panicked.
+    ///
+    /// This is the non-panicking counterpart of [`Once::get_or_init`], for targets built with `panic = "abort"`
+    /// where a poisoned [`Once`] cannot be recovered from by catching the panic. `f` is only called if the
+    /// [`Once`] is neither initialized nor poisoned.
+    ///
+    /// # Panics
+    ///
+    /// This function will still panic if called re-entrantly from within the initialization closure, as that is
+    /// a bug rather than a recoverable condition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::once::{Once, Poisoned};
+    ///
+    /// let once = Once::new();
+    /// assert_eq!(once.get_or_init_checked(|| 1), Ok(&1));
+    ///
+    /// let poisoned = Once::<u32>::new();
+    /// let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| poisoned.call_once(|| panic!())));
+    /// assert_eq!(poisoned.get_or_init_checked(|| 2), Err(Poisoned));
+    /// ```
+    pub fn get_or_init_checked<F: FnOnce() -> T>(&self, f: F) -> Result<&T, Poisoned> {
+        if self.is_poisoned() {
+            return Err(Poisoned);
+        }
+        Ok(self.call_once(f))
+    }
+
+    /// Gets the contents of the [`Once`], initializing it with `f` if it has not been initialized yet, or
+    /// returns `None` if called re-entrantly while the [`Once`] is being initialized.
+    ///
+    /// This suits initializers that may end up asking for their own value, such as tasks polled by an executor
+    /// that itself depends on the [`Once`]: the nested call can back off rather than panic. `f` is not called
+    /// in that case.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the [`Once`] previously panicked while attempting to initialize.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let once = nospin::Once::new();
+    ///
+    /// let value = once.try_get_or_init(|| {
+    ///     assert_eq!(once.try_get_or_init(|| 2), None);
+    ///     1
+    /// });
+    /// assert_eq!(value, Some(&1));
+    /// ```
+    pub fn try_get_or_init<F: FnOnce() -> T>(&self, f: F) -> Option<&T> {
+        if unsafe { *self.status.get() } == Status::Running {
+            return None;
+        }
+        Some(self.call_once(f))
+    }
+
+    /// Performs an initialization routine that may need several attempts, calling `f` up to
+    /// `attempts` times until it returns `Some` and storing that value.
+    ///
+    /// This suits initializers that poll something which may not be ready on the first read, such
+    /// as a hardware register during bring-up. There is no contention in single-threaded code, so
+    /// "retry" only ever means the closure itself declined to produce a value; nothing spins. Pass
+    /// `usize::MAX` to keep retrying until `f` succeeds.
+    ///
+    /// If every attempt returns `None` then `None` is returned and the [`Once`] is left
+    /// uninitialized. If the [`Once`] has already been initialized, `f` is not called.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the [`Once`] previously panicked while attempting
+    /// to initialize, or if called re-entrantly from within the initialization closure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let once = nospin::Once::new();
+    /// let mut reads = 0;
+    ///
+    /// let mut read_register = || {
+    ///     reads += 1;
+    ///     (reads == 3).then_some(0x42)
+    /// };
+    ///
+    /// assert_eq!(once.call_once_retry(2, &mut read_register), None);
+    /// assert_eq!(once.call_once_retry(2, &mut read_register), Some(&0x42));
+    /// assert_eq!(reads, 3);
+    /// ```
+    pub fn call_once_retry<F: FnMut() -> Option<T>>(
+        &self,
+        attempts: usize,
+        mut f: F,
+    ) -> Option<&T> {
+        self.try_call_once(|| (0..attempts).find_map(|_| f()).ok_or(()))
+            .ok()
+    }
+
     /// This method is similar to `call_once`, but allows the given closure to
     /// fail, and lets the `Once` in a uninitialized state if it does.
     ///
@@ -165,11 +477,14 @@ impl<T> Once<T> {
     ///
     /// This function will panic if the [`Once`] previously panicked while attempting
     /// to initialize. This is similar to the poisoning behaviour of `std::sync`'s
-    /// primitives.
+    /// primitives. It will also panic if called re-entrantly from within the
+    /// initialization closure of the same [`Once`].
     ///
     /// # Examples
     ///
     /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
     /// use nospin;
     ///
     /// static INIT: nospin::Once<usize> = nospin::Once::new();
@@ -182,29 +497,304 @@ impl<T> Once<T> {
     ///     // ...
     /// # Ok(2)
     /// }
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
     /// ```
     pub fn try_call_once<F: FnOnce() -> Result<T, E>, E>(&self, f: F) -> Result<&T, E> {
         unsafe {
-            if *self.panicked.get() {
-                panic!("Initialization panicked");
-            } else if self.is_completed() {
-                Ok(self.force_get())
-            } else {
-                *self.panicked.get() = true;
-                let value = f();
-                *self.panicked.get() = false;
-                (*self.data.get()).as_mut_ptr().write(value?);
-                *self.initialized.get() = true;
-                Ok(self.force_get())
+            if let Some(value) = self.begin_init() {
+                return Ok(value);
+            }
+            let poison = Poison(&self.status);
+            let value = f();
+            forget(poison);
+
+            match value {
+                Ok(value) => {
+                    debug_check!(
+                        *self.status.get() == Status::Running,
+                        "Once changed state while being initialized"
+                    );
+                    (*self.data.get()).as_mut_ptr().write(value);
+                    Ok(self.complete_init())
+                }
+                Err(err) => {
+                    debug_check!(
+                        *self.status.get() == Status::Running,
+                        "Once changed state while being initialized"
+                    );
+                    *self.status.get() = Status::Incomplete;
+                    Err(err)
+                }
             }
         }
     }
 
+    /// Performs an initialization routine once and only once, with `f` writing the value directly into the
+    /// storage of the [`Once`].
+    ///
+    /// Unlike [`Once::call_once`], the value is never built on the stack and then moved in, which matters for
+    /// very large values such as lookup tables in a `static`. If the [`Once`] has already been initialized, `f`
+    /// is not called.
+    ///
+    /// # Safety
+    ///
+    /// `f` must fully initialize the [`MaybeUninit`] it is given before returning. Should `f` panic instead,
+    /// the [`Once`] is poisoned and anything written so far is never read nor dropped.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the [`Once`] previously panicked while attempting
+    /// to initialize, or if called re-entrantly from within the initialization closure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// static TABLE: nospin::Once<[u32; 4096]> = nospin::Once::new();
+    ///
+    /// let table = unsafe {
+    ///     TABLE.call_once_in_place(|slot| {
+    ///         let entries = slot.as_mut_ptr().cast::<u32>();
+    ///         for i in 0..4096 {
+    ///             entries.add(i).write(i as u32 * 2);
+    ///         }
+    ///     })
+    /// };
+    /// assert_eq!(table[4095], 8190);
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    pub unsafe fn call_once_in_place<F: FnOnce(&mut MaybeUninit<T>)>(&self, f: F) -> &T {
+        unsafe {
+            if let Some(value) = self.begin_init() {
+                return value;
+            }
+            let poison = Poison(&self.status);
+            f(&mut *self.data.get());
+            forget(poison);
+
+            debug_check!(
+                *self.status.get() == Status::Running,
+                "Once changed state while being initialized"
+            );
+            self.complete_init()
+        }
+    }
+
+    // Returns the value if already initialized, and otherwise marks the `Once` as running its initialization.
+    unsafe fn begin_init(&self) -> Option<&T> {
+        unsafe {
+            match *self.status.get() {
+                Status::Complete => return Some(self.force_get()),
+                Status::Panicked => panic!("Initialization panicked"),
+                Status::Running => panic!("reentrant Once initialization"),
+                Status::Incomplete => {}
+            }
+
+            *self.status.get() = Status::Running;
+            #[cfg(feature = "debug_counters")]
+            {
+                *self.init_count.get() += 1;
+            }
+            None
+        }
+    }
+
+    // Marks the `Once` as initialized, once its value has been written.
+    unsafe fn complete_init(&self) -> &T {
+        unsafe {
+            *self.status.get() = Status::Complete;
+            #[cfg(feature = "async")]
+            if let Some(waker) = (*self.waker.get()).take() {
+                waker.wake();
+            }
+            self.force_get()
+        }
+    }
+
+    /// Behaves as [`Once::try_call_once`], but also runs the closure if the [`Once`] has been poisoned.
+    ///
+    /// The poison is cleared before `f` is called, and `f` is told via [`OnceState::is_poisoned`] whether it is
+    /// recovering from a previous panic. If `f` returns `Err`, the [`Once`] is left uninitialized rather than
+    /// poisoned, so that initialization can be attempted again later.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if called re-entrantly from within the initialization closure of the same
+    /// [`Once`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let once = nospin::Once::<u32>::new();
+    /// let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| once.call_once(|| panic!())));
+    ///
+    /// let retry = once.try_call_once_force(|state| {
+    ///     assert!(state.is_poisoned());
+    ///     Err::<u32, _>("still failing")
+    /// });
+    /// assert_eq!(retry, Err("still failing"));
+    ///
+    /// let retry = once.try_call_once_force(|state| {
+    ///     assert!(!state.is_poisoned());
+    ///     Ok::<_, ()>(1)
+    /// });
+    /// assert_eq!(retry, Ok(&1));
+    /// ```
+    pub fn try_call_once_force<F: FnOnce(&OnceState) -> Result<T, E>, E>(
+        &self,
+        f: F,
+    ) -> Result<&T, E> {
+        let state = OnceState {
+            poisoned: self.is_poisoned(),
+        };
+        self.clear_poison();
+        self.try_call_once(|| f(&state))
+    }
+
+    /// Initializes the [`Once`] with `value` if it has not been initialized yet.
+    ///
+    /// Returns a reference to the stored value on success. If the [`Once`] was already
+    /// initialized, both a reference to the existing value and the rejected `value` are
+    /// returned instead.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the [`Once`] previously panicked while attempting
+    /// to initialize. This is similar to the poisoning behaviour of `std::sync`'s
+    /// primitives.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin;
+    ///
+    /// let once = nospin::Once::new();
+    ///
+    /// assert_eq!(once.try_insert(1), Ok(&1));
+    /// assert_eq!(once.try_insert(2), Err((&1, 2)));
+    /// ```
+    pub fn try_insert(&self, value: T) -> Result<&T, (&T, T)> {
+        let mut value = Some(value);
+        let stored = self.call_once(|| value.take().unwrap());
+        match value {
+            None => Ok(stored),
+            Some(value) => Err((stored, value)),
+        }
+    }
+
+    /// Initializes the [`Once`] with `value` if it has not been initialized yet, and returns a
+    /// reference to whichever value is stored.
+    ///
+    /// If the [`Once`] was already initialized, `value` is dropped. Use [`Once::try_insert`] to get it
+    /// back instead.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the [`Once`] previously panicked while attempting
+    /// to initialize. This is similar to the poisoning behaviour of `std::sync`'s
+    /// primitives.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let once = nospin::Once::new();
+    ///
+    /// assert_eq!(once.set_if_empty(1), &1);
+    /// assert_eq!(once.set_if_empty(2), &1);
+    /// ```
+    pub fn set_if_empty(&self, value: T) -> &T {
+        match self.try_insert(value) {
+            Ok(stored) | Err((stored, _)) => stored,
+        }
+    }
+
     /// Returns a reference to the inner value if the [`Once`] has been initialized.
     pub fn get(&self) -> Option<&T> {
         unsafe { self.is_completed().then(|| self.force_get()) }
     }
 
+    /// Returns a reference to the inner value, panicking with `msg` if the [`Once`] hasn't been
+    /// initialized.
+    ///
+    /// This is the same as `once.get().expect(msg)`, for code that knows the [`Once`] has been
+    /// initialized by this point.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic with `msg` if the [`Once`] hasn't been initialized.
+    ///
+    /// ```
+    /// let once = nospin::Once::initialized(1);
+    /// assert_eq!(once.expect("configured during boot"), &1);
+    /// ```
+    pub fn expect(&self, msg: &str) -> &T {
+        match self.get() {
+            Some(value) => value,
+            None => panic!("{}", msg),
+        }
+    }
+
+    /// Returns a reference to the inner value, panicking if the [`Once`] hasn't been initialized.
+    ///
+    /// This is the same as `once.get().unwrap()`. Use [`Once::expect`] to control the panic
+    /// message.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the [`Once`] hasn't been initialized.
+    ///
+    /// ```
+    /// let once = nospin::Once::initialized(1);
+    /// assert_eq!(once.unwrap(), &1);
+    /// ```
+    pub fn unwrap(&self) -> &T {
+        match self.get() {
+            Some(value) => value,
+            None => panic!("called `Once::unwrap()` on an uninitialized Once"),
+        }
+    }
+
+    /// Polls the [`Once`] for its value, registering the task in `cx` to be woken once it has been
+    /// initialized.
+    ///
+    /// Only a single waker is stored: cooperative code realistically has one task waiting on a given
+    /// [`Once`] at a time, and registering a new waker replaces the previous one. A replaced waker is woken
+    /// straight away, so that its task polls again rather than waiting forever. The stored waker is woken by
+    /// whichever call to [`Once::call_once`] (or its siblings) completes the initialization.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub fn poll_initialized(&self, cx: &mut Context<'_>) -> Poll<&T> {
+        match self.get() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                let slot = unsafe { &mut *self.waker.get() };
+                let displaced = match slot {
+                    Some(waker) if waker.will_wake(cx.waker()) => None,
+                    _ => slot.replace(cx.waker().clone()),
+                };
+                // Woken only once the slot is no longer borrowed, in case the waker polls again immediately
+                if let Some(waker) = displaced {
+                    waker.wake();
+                }
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Returns the inner value if the [`Once`] has been initialized, consuming it.
+    ///
+    /// This is the same as converting the [`Once`] into an [`Option`], under the name used by `spin`.
+    #[cfg(feature = "spin_compat")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "spin_compat")))]
+    pub fn try_into_inner(self) -> Option<T> {
+        self.into()
+    }
+
     /// Returns a mutable reference to the inner value if the [`Once`] has been initialized.
     ///
     /// Because this method requires a mutable reference to the [`Once`], no synchronization
@@ -222,7 +812,7 @@ impl<T> Once<T> {
     /// However, this can be useful in some instances for exposing the `Once` to FFI or when the overhead of atomically
     /// checking initialization is unacceptable and the `Once` has already been initialized.
     pub unsafe fn get_mut_unchecked(&mut self) -> &mut T {
-        debug_assert!(
+        debug_check!(
             self.is_completed(),
             "Attempted to access an unintialized Once.  If this was to run without debug checks, this would be undefined behavior.  This is a serious bug and you must fix it.",
         );
@@ -238,7 +828,7 @@ impl<T> Once<T> {
     /// However, this can be useful in some instances for exposing the `Once` to FFI or when the overhead of atomically
     /// checking initialization is unacceptable and the `Once` has already been initialized.
     pub unsafe fn get_unchecked(&self) -> &T {
-        debug_assert!(
+        debug_check!(
             self.is_completed(),
             "Attempted to access an unintialized Once.  If this was to run without debug checks, this would be undefined behavior.  This is a serious bug and you must fix it.",
         );
@@ -248,12 +838,60 @@ impl<T> Once<T> {
     /// Creates a new initialized [`Once`].
     pub const fn initialized(data: T) -> Self {
         Self {
-            initialized: UnsafeCell::new(true),
-            panicked: UnsafeCell::new(false),
+            status: UnsafeCell::new(Status::Complete),
+            #[cfg(feature = "debug_counters")]
+            init_count: UnsafeCell::new(0),
+            #[cfg(feature = "async")]
+            waker: UnsafeCell::new(None),
             data: UnsafeCell::new(MaybeUninit::new(data)),
         }
     }
 
+    /// Creates a new [`Once`] initialized with the value returned by `f`, which is called immediately.
+    ///
+    /// This is the same as [`Once::initialized`]`(f())`, and is clearer than creating a [`Once`] and then
+    /// calling [`Once::call_once`] on it when the value is available straight away.
+    ///
+    /// ```
+    /// let once = nospin::Once::new_initialized_with(|| vec![1, 2]);
+    /// assert!(once.is_completed());
+    /// assert_eq!(once.call_once(|| unreachable!()), &[1, 2]);
+    /// ```
+    pub fn new_initialized_with<F: FnOnce() -> T>(f: F) -> Self {
+        Self::initialized(f())
+    }
+
+    /// Drops the inner value in place if the [`Once`] has been initialized, returning it to
+    /// its uninitialized state.
+    ///
+    /// This is the destructive counterpart to [`Once::initialized`], useful when FFI controls the
+    /// lifetime of the value but only holds a shared reference to the [`Once`]. A poisoned [`Once`]
+    /// is left untouched.
+    ///
+    /// # Safety
+    ///
+    /// This is *extremely* unsafe if any references to the inner value are still live, as they will
+    /// be left dangling. Callers must guarantee that no reference obtained from this [`Once`] (via
+    /// [`Once::call_once`], [`Once::get`] or otherwise) outlives this call.
+    ///
+    /// ```
+    /// let once = nospin::Once::new();
+    /// once.call_once(|| String::from("hello"));
+    ///
+    /// unsafe { once.force_drop() };
+    /// assert!(!once.is_completed());
+    /// assert_eq!(once.call_once(|| String::from("again")), "again");
+    /// ```
+    pub unsafe fn force_drop(&self) {
+        if self.is_completed() {
+            unsafe {
+                // Reset first, so a panicking destructor cannot leave the value marked as initialized
+                *self.status.get() = Status::Incomplete;
+                core::ptr::drop_in_place((*self.data.get()).as_mut_ptr());
+            }
+        }
+    }
+
     /// Returns a the inner value if the [`Once`] has been initialized.
     /// # Safety
     ///
@@ -262,18 +900,89 @@ impl<T> Once<T> {
     /// This can be useful, if `Once` has already been initialized, and you want to bypass an
     /// option check.
     pub unsafe fn into_inner_unchecked(self) -> T {
-        debug_assert!(
+        debug_check!(
             self.is_completed(),
             "Attempted to access an unintialized Once.  If this was to run without debug checks, this would be undefined behavior.  This is a serious bug and you must fix it.",
         );
         unsafe { self.force_into_inner() }
     }
 
+    /// Maps an initialized [`Once<T>`] to an initialized [`Once<U>`] by applying `f` to its value, or returns an
+    /// uninitialized [`Once<U>`] otherwise.
+    ///
+    /// The value is moved out of `self`, so it is passed to `f` exactly once and never dropped by `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nospin::Once;
+    ///
+    /// let once = Once::initialized(String::from("hello"));
+    /// assert_eq!(once.map_into(|s| s.len()).get(), Some(&5));
+    ///
+    /// let empty = Once::<String>::new();
+    /// assert!(!empty.map_into(|s| s.len()).is_completed());
+    /// ```
+    pub fn map_into<U, F: FnOnce(T) -> U>(self, f: F) -> Once<U> {
+        match Option::<T>::from(self) {
+            Some(value) => Once::initialized(f(value)),
+            None => Once::new(),
+        }
+    }
+
+    /// Returns the number of times an initialization closure has been run on this [`Once`].
+    ///
+    /// In correct usage this is `0` or `1`, though closures that fail via [`Once::try_call_once`] are
+    /// counted too. Re-entrant calls made from within an initialization closure panic before they are
+    /// counted.
+    #[cfg(feature = "debug_counters")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug_counters")))]
+    pub fn init_count(&self) -> usize {
+        unsafe { *self.init_count.get() }
+    }
+
     /// Checks whether the value has been initialized.
     ///
     /// It is safe to access the value directly via [`get_unchecked`](Self::get_unchecked) if this returns true.
     pub fn is_completed(&self) -> bool {
-        unsafe { *self.initialized.get() }
+        unsafe { *self.status.get() == Status::Complete }
+    }
+
+    /// Checks whether a previous initialization closure panicked, poisoning the [`Once`].
+    ///
+    /// ```
+    /// let once = nospin::Once::<u32>::new();
+    ///
+    /// let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| once.call_once(|| panic!())));
+    /// assert!(once.is_poisoned());
+    /// ```
+    pub fn is_poisoned(&self) -> bool {
+        unsafe { *self.status.get() == Status::Panicked }
+    }
+
+    /// Clears the poisoned state left by a panicking initialization closure, so that the next call to
+    /// [`Once::call_once`] runs its closure instead of panicking.
+    ///
+    /// This does nothing if the [`Once`] is not poisoned. There is no other thread that could be initializing
+    /// the [`Once`] concurrently, but calling this from within an initialization closure of the same [`Once`] is
+    /// a logic error: the [`Once`] is not poisoned at that point, so nothing is cleared.
+    ///
+    /// ```
+    /// let once = nospin::Once::<u32>::new();
+    ///
+    /// let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| once.call_once(|| panic!())));
+    /// assert!(once.is_poisoned());
+    ///
+    /// once.clear_poison();
+    /// assert_eq!(once.call_once(|| 1), &1);
+    /// ```
+    pub fn clear_poison(&self) {
+        unsafe {
+            let status = &mut *self.status.get();
+            if *status == Status::Panicked {
+                *status = Status::Incomplete;
+            }
+        }
     }
 
     /// Behaves as [`Once::get`], but provided for API compatibility with `spin``.
@@ -300,6 +1009,31 @@ impl<T> Once<T> {
         self.get()
             .expect("Waited on uninitialized Once, who are you waiting for?")
     }
+
+    /// Behaves as [`Once::get_mut`], but provided for symmetry with [`Once::poll`].
+    pub fn poll_mut(&mut self) -> Option<&mut T> {
+        self.get_mut()
+    }
+
+    /// Returns a mutable reference to the inner value, panicking if the [`Once`] hasn't been
+    /// initialized. This is the mutable counterpart of [`Once::wait`].
+    ///
+    /// Because this method requires a mutable reference to the [`Once`], it is zero-cost.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the [`Once`] hasn't been initialized.
+    ///
+    /// ```
+    /// let mut once = nospin::Once::initialized(1);
+    ///
+    /// *once.wait_mut() += 1;
+    /// assert_eq!(once.wait(), &2);
+    /// ```
+    pub fn wait_mut(&mut self) -> &mut T {
+        self.get_mut()
+            .expect("Waited on uninitialized Once, who are you waiting for?")
+    }
 }
 
 impl<T> Default for Once<T> {
@@ -313,12 +1047,15 @@ mod tests {
     use std::prelude::v1::*;
     use std::sync::atomic::Ordering;
 
+    use std::sync::Arc;
     use std::sync::atomic::AtomicU32;
+    #[cfg(not(feature = "strict_send_sync"))]
     use std::sync::mpsc::channel;
     use std::thread;
 
     use super::*;
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn smoke_once() {
         static O: Once = Once::new();
@@ -329,6 +1066,7 @@ mod tests {
         assert_eq!(a, 1);
     }
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn smoke_once_value() {
         static O: Once<usize> = Once::new();
@@ -338,6 +1076,7 @@ mod tests {
         assert_eq!(*b, 1);
     }
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn stampede_once() {
         static O: Once = Once::new();
@@ -379,6 +1118,7 @@ mod tests {
         }
     }
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn get() {
         static INIT: Once<usize> = Once::new();
@@ -388,6 +1128,7 @@ mod tests {
         assert_eq!(INIT.get().copied(), Some(2));
     }
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn get_no_wait() {
         static INIT: Once<usize> = Once::new();
@@ -404,6 +1145,33 @@ mod tests {
         t.join().unwrap();
     }
 
+    #[test]
+    fn try_insert() {
+        let once = Once::<String>::new();
+
+        assert_eq!(once.try_insert("a".to_string()), Ok(&"a".to_string()));
+        match once.try_insert("b".to_string()) {
+            Err((existing, rejected)) => {
+                assert_eq!(existing, "a");
+                assert_eq!(rejected, "b");
+            }
+            Ok(_) => panic!("second insert should fail"),
+        }
+        assert_eq!(once.get().map(String::as_str), Some("a"));
+    }
+
+    #[cfg(feature = "debug_counters")]
+    #[test]
+    fn init_count() {
+        let once = Once::<u32>::new();
+        assert!(once.try_call_once(|| Err(())).is_err());
+        assert_eq!(once.init_count(), 1);
+        once.call_once(|| 1);
+        once.call_once(|| 2);
+        assert_eq!(once.init_count(), 2);
+    }
+
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn poll() {
         static INIT: Once<usize> = Once::new();
@@ -413,25 +1181,23 @@ mod tests {
         assert_eq!(INIT.poll().copied(), Some(3));
     }
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn wait() {
         static INIT: Once<usize> = Once::new();
 
+        assert!(INIT.poll().is_none());
+        INIT.call_once(|| 3);
+
         let t = std::thread::spawn(|| {
             assert_eq!(*INIT.wait(), 3);
             assert!(INIT.is_completed());
         });
 
-        for _ in 0..4 {
-            thread::yield_now()
-        }
-
-        assert!(INIT.poll().is_none());
-        INIT.call_once(|| 3);
-
         t.join().unwrap();
     }
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn panic() {
         use std::panic;
@@ -451,6 +1217,324 @@ mod tests {
         assert!(t.is_err());
     }
 
+    #[test]
+    #[should_panic(expected = "reentrant Once initialization")]
+    fn reentrant() {
+        let once = Once::<u32>::new();
+        once.call_once(|| *once.call_once(|| 1) + 1);
+    }
+
+    #[test]
+    fn panic_after_reentrant() {
+        use std::panic;
+
+        let once = Once::<u32>::new();
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            once.call_once(|| *once.call_once(|| 1) + 1);
+        }));
+        assert!(t.is_err());
+
+        // The outer initialization was interrupted, so the `Once` is poisoned
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            once.call_once(|| 1);
+        }));
+        let msg = *t.unwrap_err().downcast::<&str>().unwrap();
+        assert_eq!(msg, "Initialization panicked");
+    }
+
+    #[cfg(feature = "spin_compat")]
+    #[test]
+    fn try_into_inner() {
+        assert_eq!(Once::<u32>::new().try_into_inner(), None);
+        assert_eq!(Once::initialized(1).try_into_inner(), Some(1));
+    }
+
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn eq_hash() {
+        use std::collections::HashSet;
+
+        let set: HashSet<Once<u32>> = [Once::new(), Once::initialized(1), Once::initialized(1)]
+            .into_iter()
+            .collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&Once::new()));
+        assert!(!set.contains(&Once::initialized(2)));
+
+        // Poisoned counts as uninitialized
+        let poisoned = Once::<u32>::new();
+        let t = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            poisoned.call_once(|| panic!());
+        }));
+        assert!(t.is_err());
+        assert!(set.contains(&poisoned));
+    }
+
+    #[test]
+    fn mut_accessors() {
+        use std::panic;
+
+        let mut once = Once::<u32>::new();
+        assert_eq!(once.poll_mut(), None);
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            once.wait_mut();
+        }));
+        assert!(t.is_err());
+
+        *once.get_mut_or_init(|| 1) += 1;
+        *once.get_mut_or_init(|| 5) += 1;
+        *once.wait_mut() += 1;
+        assert_eq!(once.poll_mut(), Some(&mut 4));
+    }
+
+    #[test]
+    fn call_once_in_place() {
+        use std::panic;
+
+        let once = Once::<[u8; 3]>::new();
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| unsafe {
+            once.call_once_in_place(|_| panic!());
+        }));
+        assert!(t.is_err());
+        assert!(once.is_poisoned());
+
+        let once = Once::new();
+        let value = unsafe { once.call_once_in_place(|slot| _ = slot.write([1, 2, 3])) };
+        assert_eq!(value, &[1, 2, 3]);
+        let value = unsafe { once.call_once_in_place(|_| unreachable!()) };
+        assert_eq!(value, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn try_get_or_init() {
+        use std::panic;
+
+        let once = Once::new();
+        let mut nested = Some(&0);
+        assert_eq!(
+            once.try_get_or_init(|| {
+                nested = once.try_get_or_init(|| unreachable!());
+                1
+            }),
+            Some(&1)
+        );
+        assert_eq!(nested, None);
+        assert_eq!(once.try_get_or_init(|| unreachable!()), Some(&1));
+
+        let poisoned = Once::<u32>::new();
+        let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| poisoned.call_once(|| panic!())));
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| poisoned.try_get_or_init(|| 1)));
+        assert!(t.is_err());
+    }
+
+    #[test]
+    fn expect_unwrap() {
+        use std::panic;
+
+        let once = Once::<u32>::new();
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            once.expect("not yet configured");
+        }));
+        let msg = t.unwrap_err().downcast::<String>().unwrap();
+        assert_eq!(*msg, "not yet configured");
+        assert!(panic::catch_unwind(panic::AssertUnwindSafe(|| once.unwrap())).is_err());
+
+        once.call_once(|| 2);
+        assert_eq!(once.expect("configured"), &2);
+        assert_eq!(once.unwrap(), &2);
+    }
+
+    #[test]
+    fn clear_poison() {
+        use std::panic;
+
+        let once = Once::<u32>::new();
+        once.clear_poison();
+        assert!(!once.is_completed());
+
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            once.call_once(|| panic!());
+        }));
+        assert!(t.is_err());
+
+        once.clear_poison();
+        assert!(!once.is_poisoned());
+        assert_eq!(once.get_or_init_checked(|| 2), Ok(&2));
+
+        // An initialized `Once` is left alone
+        once.clear_poison();
+        assert_eq!(once.get(), Some(&2));
+    }
+
+    #[test]
+    fn try_call_once_force() {
+        use std::panic;
+
+        let once = Once::<u32>::new();
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            once.call_once(|| panic!());
+        }));
+        assert!(t.is_err());
+
+        // A panic during recovery poisons the `Once` again
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            let _ = once.try_call_once_force(|_| -> Result<u32, ()> { panic!() });
+        }));
+        assert!(t.is_err());
+        assert!(once.is_poisoned());
+
+        assert_eq!(
+            once.try_call_once_force(|s| Err(s.is_poisoned())),
+            Err(true)
+        );
+        assert!(!once.is_poisoned());
+        assert_eq!(
+            once.try_call_once_force(|s| Err(s.is_poisoned())),
+            Err(false)
+        );
+
+        assert_eq!(once.try_call_once_force(|_| Ok::<_, ()>(1)), Ok(&1));
+        assert_eq!(once.try_call_once_force(|_| Ok::<_, ()>(2)), Ok(&1));
+    }
+
+    #[test]
+    fn panic_drops_partial_init() {
+        use std::panic;
+
+        let drops = Arc::new(AtomicU32::new(0));
+        let once = Once::<Counted>::new();
+
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            once.get_or_init(|| {
+                let _partial = Counted(drops.clone());
+                panic!();
+            });
+        }));
+        assert!(t.is_err());
+
+        // The partially constructed value was dropped by unwinding and never stored
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+        assert!(once.get().is_none());
+
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            once.get_or_init(|| Counted(drops.clone()));
+        }));
+        assert!(t.is_err());
+
+        drop(once);
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn call_once_retry() {
+        let once = Once::new();
+        let mut calls = 0;
+
+        assert_eq!(once.call_once_retry(0, || unreachable!()), None);
+        assert_eq!(
+            once.call_once_retry(usize::MAX, || {
+                calls += 1;
+                (calls == 5).then_some(calls)
+            }),
+            Some(&5)
+        );
+        assert_eq!(once.call_once_retry(1, || unreachable!()), Some(&5));
+    }
+
+    #[test]
+    fn force_drop() {
+        let drops = Arc::new(AtomicU32::new(0));
+
+        let once = Once::<_>::new();
+        once.call_once(|| Counted(drops.clone()));
+        unsafe { once.force_drop() };
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+        assert!(!once.is_completed());
+
+        // Already uninitialized, nothing to drop
+        unsafe { once.force_drop() };
+        drop(once);
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn map_into() {
+        let drops = Arc::new(AtomicU32::new(0));
+
+        let once = Once::initialized(Counted(drops.clone()));
+        let mapped = once.map_into(|counted| (counted, 1));
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+        assert_eq!(mapped.get().map(|(_, n)| *n), Some(1));
+        drop(mapped);
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn option_conversions() {
+        let drops = Arc::new(AtomicU32::new(0));
+
+        let once: Once<Counted> = Once::from(Some(Counted(drops.clone())));
+        assert!(once.is_completed());
+        let value: Option<Counted> = once.into();
+        assert!(value.is_some());
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+        drop(value);
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+        let once = Once::<Counted>::from(None);
+        assert!(!once.is_completed());
+        assert!(Option::<Counted>::from(once).is_none());
+
+        let once = Once::initialized(Counted(drops.clone()));
+        drop(unsafe { once.into_inner_unchecked() });
+        assert_eq!(drops.load(Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn poll_initialized() {
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct Flag(AtomicU32);
+
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let flag = Arc::new(Flag(AtomicU32::new(0)));
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let once = Once::new();
+        assert_eq!(once.poll_initialized(&mut cx), Poll::Pending);
+        assert_eq!(once.poll_initialized(&mut cx), Poll::Pending);
+        assert_eq!(flag.0.load(Ordering::SeqCst), 0);
+
+        once.call_once(|| 5);
+        assert_eq!(flag.0.load(Ordering::SeqCst), 1);
+        assert_eq!(once.poll_initialized(&mut cx), Poll::Ready(&5));
+        assert_eq!(flag.0.load(Ordering::SeqCst), 1);
+
+        // A second task waiting on the same `Once` wakes the first rather than silently dropping its waker
+        let other = Arc::new(Flag(AtomicU32::new(0)));
+        let other_waker = Waker::from(other.clone());
+        let once = Once::new();
+        assert_eq!(once.poll_initialized(&mut cx), Poll::Pending);
+        assert_eq!(
+            once.poll_initialized(&mut Context::from_waker(&other_waker)),
+            Poll::Pending
+        );
+        assert_eq!(flag.0.load(Ordering::SeqCst), 2);
+        assert_eq!(other.0.load(Ordering::SeqCst), 0);
+
+        once.call_once(|| 6);
+        assert_eq!(other.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn init_constant() {
         static O: Once = Once::INIT;
@@ -461,6 +1545,15 @@ mod tests {
         assert_eq!(a, 1);
     }
 
+    // Counts its drops without the global state of `DropTest`, so tests using it may run concurrently
+    struct Counted(Arc<AtomicU32>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
     static mut CALLED: bool = false;
 
     struct DropTest {}
@@ -519,6 +1612,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg_attr(feature = "strict_send_sync", allow(clippy::arc_with_non_send_sync))]
     fn call_once_test() {
         for _ in 0..20 {
             use std::sync::Arc;
@@ -0,0 +1,308 @@
+//! Locks that may be acquired multiple times by their current holder.
+use {
+    crate::traits::Guard,
+    alloc::fmt,
+    core::{cell::UnsafeCell, ops::Deref},
+};
+
+/// A reentrant Mutex that is NOT thread safe which allows the lock to be acquired again
+/// while it is already held, suitable only for single-threaded environments.
+///
+/// Since several guards may be alive at the same time, only shared access to the data is
+/// provided. Interior mutability (e.g. a `Cell` or `RefCell`) can be used to mutate it.
+///
+/// # Example
+///
+/// ```
+/// use core::cell::Cell;
+/// use nospin;
+///
+/// let lock = nospin::ReentrantMutex::new(Cell::new(0));
+///
+/// let outer = lock.lock();
+/// let inner = lock.lock();
+/// inner.set(2);
+/// assert_eq!(outer.get(), 2);
+/// assert_eq!(inner.depth(), 2);
+/// ```
+pub struct ReentrantMutex<T: ?Sized> {
+    depth: UnsafeCell<usize>,
+    data: UnsafeCell<T>,
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ReentrantMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let guard = self.lock();
+        write!(f, "ReentrantMutex {{ data: ")
+            .and_then(|()| (*guard).fmt(f))
+            .and_then(|()| write!(f, " }}"))
+    }
+}
+
+impl<T: Default> Default for ReentrantMutex<T> {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<T> From<T> for ReentrantMutex<T> {
+    fn from(data: T) -> Self {
+        Self::new(data)
+    }
+}
+
+#[cfg(not(feature = "strict_send_sync"))]
+unsafe impl<T: ?Sized + Send> Sync for ReentrantMutex<T> {}
+#[cfg(not(feature = "strict_send_sync"))]
+unsafe impl<T: ?Sized + Send> Send for ReentrantMutex<T> {}
+
+impl<T> ReentrantMutex<T> {
+    /// Creates a new [`ReentrantMutex`] wrapping the supplied data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// use nospin::ReentrantMutex;
+    ///
+    /// static MUTEX: ReentrantMutex<()> = ReentrantMutex::new(());
+    ///
+    /// fn demo() {
+    ///     let lock = MUTEX.lock();
+    ///     // do something with lock
+    ///     drop(lock);
+    /// }
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    #[inline(always)]
+    pub const fn new(data: T) -> ReentrantMutex<T> {
+        ReentrantMutex {
+            depth: UnsafeCell::new(0),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Consumes this [`ReentrantMutex`] and unwraps the underlying data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let lock = nospin::ReentrantMutex::new(42);
+    /// assert_eq!(42, lock.into_inner());
+    /// ```
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        // We know statically that there are no outstanding references to
+        // `self` so there's no need to lock.
+        let ReentrantMutex { data, .. } = self;
+        data.into_inner()
+    }
+}
+
+impl<T: ?Sized> ReentrantMutex<T> {
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the [`ReentrantMutex`] mutably, no actual locking needs to take
+    /// place -- the mutable borrow statically guarantees no locks exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut lock = nospin::ReentrantMutex::new(0);
+    /// *lock.get_mut() = 10;
+    /// assert_eq!(*lock.lock(), 10);
+    /// ```
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut T {
+        // We know statically that there are no other references to `self`, so
+        // there's no need to lock the inner mutex.
+        unsafe { &mut *self.data.get() }
+    }
+
+    /// Returns `true` if the lock is currently held.
+    ///
+    /// # Safety
+    ///
+    /// This function provides no synchronization guarantees and so its result should be considered 'out of date'
+    /// the instant it is called. Do not use it for synchronization purposes. However, it may be useful as a heuristic.
+    #[inline(always)]
+    pub fn is_locked(&self) -> bool {
+        unsafe { *self.depth.get() != 0 }
+    }
+
+    /// Locks the [`ReentrantMutex`] and returns a guard that permits shared access to the inner data.
+    ///
+    /// Unlike [`Mutex::lock`](crate::Mutex::lock) this will not panic if the lock is already held,
+    /// instead the recursion depth is incremented. The lock is released once every guard has been dropped.
+    ///
+    /// ```
+    /// let lock = nospin::ReentrantMutex::new(0);
+    /// {
+    ///     let data = lock.lock();
+    ///     // The lock may be taken again while it is held
+    ///     let again = lock.lock();
+    ///     assert_eq!(*data, *again);
+    /// }
+    /// assert!(!lock.is_locked());
+    /// ```
+    #[inline(always)]
+    pub fn lock(&self) -> ReentrantMutexGuard<'_, T> {
+        unsafe {
+            let depth = &mut *self.depth.get();
+            *depth = depth
+                .checked_add(1)
+                .expect("ReentrantMutex recursion depth overflowed");
+        }
+        ReentrantMutexGuard { lock: self }
+    }
+
+    /// Locks the [`ReentrantMutex`] and runs `f` with shared access to the inner data, returning its result.
+    ///
+    /// ```
+    /// let lock = nospin::ReentrantMutex::new(vec![1, 2, 3]);
+    ///
+    /// let len = lock.with(|v| lock.with(|w| v.len() + w.len()));
+    /// assert_eq!(len, 6);
+    /// ```
+    #[inline(always)]
+    pub fn with<R, F: FnOnce(&T) -> R>(&self, f: F) -> R {
+        f(&self.lock())
+    }
+}
+
+/// A guard that provides shared access to the data of a [`ReentrantMutex`].
+///
+/// When the guard falls out of scope it will decrement the recursion depth,
+/// releasing the lock once the depth reaches zero.
+pub struct ReentrantMutexGuard<'a, T: 'a + ?Sized> {
+    lock: &'a ReentrantMutex<T>,
+}
+
+impl<T: ?Sized> ReentrantMutexGuard<'_, T> {
+    /// Returns the current recursion depth of the lock, that is the number of guards alive for it
+    /// (including this one).
+    ///
+    /// ```
+    /// let lock = nospin::ReentrantMutex::new(());
+    ///
+    /// let outer = lock.lock();
+    /// assert_eq!(outer.depth(), 1);
+    /// {
+    ///     let inner = lock.lock();
+    ///     assert_eq!(inner.depth(), 2);
+    /// }
+    /// assert_eq!(outer.depth(), 1);
+    /// ```
+    #[inline(always)]
+    pub fn depth(&self) -> usize {
+        unsafe { *self.lock.depth.get() }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ReentrantMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if cfg!(feature = "verbose_debug") {
+            f.debug_tuple("ReentrantMutexGuard")
+                .field(&&**self)
+                .finish()
+        } else {
+            fmt::Debug::fmt(&**self, f)
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for ReentrantMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> Deref for ReentrantMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Guard for ReentrantMutexGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn get(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> Drop for ReentrantMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            debug_check!(*self.lock.depth.get() > 0);
+            *self.lock.depth.get() -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::v1::*;
+
+    use std::cell::RefCell;
+
+    type ReentrantMutex<T> = super::ReentrantMutex<T>;
+
+    #[test]
+    fn smoke() {
+        let m = ReentrantMutex::new(());
+        {
+            let a = m.lock();
+            {
+                let b = m.lock();
+                {
+                    let c = m.lock();
+                    assert_eq!(c.depth(), 3);
+                }
+                assert_eq!(b.depth(), 2);
+            }
+            assert_eq!(a.depth(), 1);
+        }
+        assert!(!m.is_locked());
+    }
+
+    #[test]
+    fn is_mutex() {
+        let m = ReentrantMutex::new(RefCell::new(0));
+        let lock = m.lock();
+        {
+            let lock2 = m.lock();
+            *lock2.borrow_mut() = 4;
+        }
+        assert_eq!(*lock.borrow(), 4);
+    }
+
+    #[test]
+    fn with() {
+        let m = ReentrantMutex::new(RefCell::new(Vec::new()));
+
+        fn visit(m: &ReentrantMutex<RefCell<Vec<usize>>>, remaining: usize) {
+            m.with(|v| v.borrow_mut().push(m.lock().depth()));
+            if remaining > 0 {
+                let _g = m.lock();
+                visit(m, remaining - 1);
+            }
+        }
+
+        visit(&m, 3);
+        assert_eq!(*m.lock().borrow(), [2, 3, 4, 5]);
+        assert!(!m.is_locked());
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let m = ReentrantMutex::new(RefCell::new(10));
+        assert_eq!(m.into_inner().into_inner(), 10);
+    }
+}
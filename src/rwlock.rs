@@ -1,13 +1,27 @@
 //! A lock that provides data access to either one writer or many readers.
-use core::{
-    cell::UnsafeCell,
-    fmt,
-    mem::{ManuallyDrop, drop, forget},
-    ops::{Deref, DerefMut},
+use {
+    crate::traits::{Guard, GuardMut, TryLock},
+    alloc::boxed::Box,
+    core::{
+        cell::UnsafeCell,
+        cmp, fmt,
+        mem::{ManuallyDrop, drop, forget},
+        ops::{BitOr, ControlFlow, Deref, DerefMut},
+        ptr::NonNull,
+    },
 };
 
-struct NonAtomicUsize {
-    value: UnsafeCell<usize>,
+#[cfg(feature = "lock_order")]
+use crate::lock_order::{self, LockId};
+
+// The integer type backing the lock state.
+#[cfg(not(feature = "small_state"))]
+type State = usize;
+#[cfg(feature = "small_state")]
+type State = u16;
+
+struct NonAtomicState {
+    value: UnsafeCell<State>,
 }
 
 #[non_exhaustive]
@@ -28,75 +42,109 @@ impl Ordering {
     pub const SeqCst: Ordering = Ordering;
 }
 
-impl NonAtomicUsize {
-    pub const fn new(value: usize) -> NonAtomicUsize {
+impl NonAtomicState {
+    pub const fn new(value: State) -> NonAtomicState {
         Self {
             value: UnsafeCell::new(value),
         }
     }
 
-    pub fn fetch_add(&self, value: usize, _order: Ordering) -> usize {
+    pub fn fetch_add(&self, value: State, _order: Ordering) -> State {
         self.update_with(|x| x + value)
     }
 
-    pub fn fetch_sub(&self, value: usize, _order: Ordering) -> usize {
+    pub fn fetch_sub(&self, value: State, _order: Ordering) -> State {
         self.update_with(|x| x - value)
     }
 
-    pub fn fetch_and(&self, value: usize, _order: Ordering) -> usize {
+    pub fn fetch_and(&self, value: State, _order: Ordering) -> State {
         self.update_with(|x| x & value)
     }
 
-    pub fn fetch_or(&self, value: usize, _order: Ordering) -> usize {
+    pub fn fetch_or(&self, value: State, _order: Ordering) -> State {
         self.update_with(|x| x | value)
     }
 
     #[inline]
-    fn update_with<F>(&self, f: F) -> usize
+    fn update_with<F>(&self, f: F) -> State
     where
-        F: Fn(usize) -> usize,
+        F: Fn(State) -> State,
     {
-        let value = self.get();
-        self.set(f(value));
-        value
+        crate::interrupt_free(|| {
+            let value = self.get();
+            self.set(f(value));
+            value
+        })
     }
 
     #[inline]
-    fn get(&self) -> usize {
+    fn get(&self) -> State {
         unsafe { *self.value.get() }
     }
 
-    fn set(&self, value: usize) {
+    fn set(&self, value: State) {
         unsafe { *self.value.get() = value }
     }
 
     #[inline]
-    pub fn load(&self, _order: Ordering) -> usize {
+    pub fn load(&self, _order: Ordering) -> State {
         self.get()
     }
 
     #[inline]
-    pub fn store(&self, value: usize, _order: Ordering) {
+    pub fn store(&self, value: State, _order: Ordering) {
         self.set(value);
     }
 
     pub fn compare_exchange(
         &self,
-        current: usize,
-        new: usize,
+        current: State,
+        new: State,
         _success: Ordering,
         _failure: Ordering,
-    ) -> Result<usize, usize> {
-        let value = self.get();
-        if value == current {
-            self.set(new);
-            Ok(new)
+    ) -> Result<State, State> {
+        crate::interrupt_free(|| {
+            let value = self.get();
+            if value == current {
+                self.set(new);
+                Ok(new)
+            } else {
+                Err(value)
+            }
+        })
+    }
+
+    // Acquire a read lock, returning the previous lock value and whether the reader was counted.
+    //
+    // A reader is only ever left uncounted when the `saturating_readers` feature is enabled.
+    fn acquire_reader(&self) -> (State, bool) {
+        // Checked before adding, as a full count could otherwise overflow into the `SATURATED` bit
+        let value = self.update_with(|x| {
+            if x & !SATURATED < MAX_READERS * READER {
+                x + READER
+            } else {
+                x
+            }
+        });
+
+        if value & !SATURATED < MAX_READERS * READER {
+            (value, true)
         } else {
-            Err(value)
+            (value, too_many_readers())
         }
     }
 }
 
+// Called once the reader cap has been reached. Panics, or returns `false` to leave the reader uncounted when the
+// `saturating_readers` feature is enabled.
+fn too_many_readers() -> bool {
+    if cfg!(feature = "saturating_readers") {
+        false
+    } else {
+        panic!("Too many lock readers, cannot safely proceed");
+    }
+}
+
 /// A lock that provides data access to either one writer or many readers.
 ///
 /// This lock behaves in a similar manner to its namesake `std::sync::RwLock` but
@@ -127,6 +175,17 @@ impl NonAtomicUsize {
 /// when there are existing readers. However if the lock is that highly contended and writes are
 /// crucial then this implementation may be a poor choice.
 ///
+/// The number of readers is capped at a very large value, beyond which acquiring another reader
+/// panics. With the `saturating_readers` feature enabled, readers acquired beyond the cap are
+/// instead not counted at all (and do not decrement the count when dropped). This means
+/// [`RwLock::reader_count`] under-reports while such readers exist. As there is then no telling
+/// when the last reader is gone, a lock that has saturated can never be write locked or upgraded
+/// again.
+///
+/// The lock state is stored in a `usize`. With the `small_state` feature enabled a `u16` is used
+/// instead, which saves space in tables of locks on targets where `usize` is wider, but lowers the
+/// reader cap to 8191.
+///
 /// # Examples
 ///
 /// ```
@@ -150,21 +209,33 @@ impl NonAtomicUsize {
 /// } // write lock is dropped here
 /// ```
 pub struct RwLock<T: ?Sized> {
-    lock: NonAtomicUsize,
+    lock: NonAtomicState,
+    #[cfg(feature = "lock_order")]
+    lock_id: LockId,
     data: UnsafeCell<T>,
 }
 
-const READER: usize = 1 << 2;
-const UPGRADED: usize = 1 << 1;
-const WRITER: usize = 1;
+const READER: State = 1 << 2;
+const UPGRADED: State = 1 << 1;
+const WRITER: State = 1;
+
+// An arbitrary cap that allows us to catch overflows long before they happen
+const MAX_READERS: State = State::MAX / READER / 2;
+
+// Set above the reader count once a read guard has been handed out uncounted by `saturating_readers`. Nothing tells
+// when such a reader is dropped, so the bit is never cleared, and since writers only acquire the lock from an exact
+// state it keeps them out for good.
+const SATURATED: State = !(State::MAX >> 1);
 
 /// A guard that provides immutable data access.
 ///
 /// When the guard falls out of scope it will decrement the read count,
 /// potentially releasing the lock.
 pub struct RwLockReadGuard<'a, T: 'a + ?Sized> {
-    lock: &'a NonAtomicUsize,
+    lock: &'a NonAtomicState,
     data: *const T,
+    #[cfg(feature = "saturating_readers")]
+    counted: bool,
 }
 
 /// A guard that provides mutable data access.
@@ -187,25 +258,179 @@ pub struct RwLockUpgradableGuard<'a, T: 'a + ?Sized> {
     data: *const T,
 }
 
+/// A guard returned by [`RwLockReadGuard::map`] that provides immutable access to a component of the data.
+///
+/// When the guard falls out of scope it will decrement the read count,
+/// potentially releasing the lock.
+pub struct MappedRwLockReadGuard<'a, T: 'a + ?Sized> {
+    lock: &'a NonAtomicState,
+    data: *const T,
+    #[cfg(feature = "saturating_readers")]
+    counted: bool,
+}
+
+/// A guard returned by [`RwLockWriteGuard::map`] that provides mutable access to a component of the data.
+///
+/// When the guard falls out of scope it will release the lock.
+pub struct MappedRwLockWriteGuard<'a, T: 'a + ?Sized> {
+    lock: &'a NonAtomicState,
+    data: *mut T,
+}
+
+/// A decoded snapshot of the state of an [`RwLock`], returned by [`RwLock::state_snapshot`].
+///
+/// Note that an upgradeable guard that failed to be acquired while a writer held the lock leaves its bit set
+/// until the writer releases it, so `has_upgradable` may be `true` alongside `has_writer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RwLockState {
+    /// The number of readers holding the lock, not counting an upgradeable guard.
+    pub readers: usize,
+    /// Whether a writer holds the lock.
+    pub has_writer: bool,
+    /// Whether an upgradeable guard holds the lock.
+    pub has_upgradable: bool,
+}
+
+/// The set of ways an [`RwLock`] is currently held, returned by [`RwLock::flags`].
+///
+/// The flags mirror the low bits of the lock's internal state word, except that [`RwLockFlags::READER`]
+/// is set for any non-zero number of readers rather than being a single counter bit.
+///
+/// ```
+/// use nospin::rwlock::RwLockFlags;
+///
+/// let lock = nospin::RwLock::new(());
+/// core::mem::forget(lock.read());
+/// core::mem::forget(lock.upgradeable_read());
+///
+/// assert!(lock.flags().contains(RwLockFlags::READER | RwLockFlags::UPGRADED));
+/// assert!(!lock.flags().contains(RwLockFlags::WRITER));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RwLockFlags(u8);
+
+impl RwLockFlags {
+    /// A writer holds the lock.
+    pub const WRITER: Self = Self(WRITER as u8);
+    /// An upgradeable guard holds the lock.
+    pub const UPGRADED: Self = Self(UPGRADED as u8);
+    /// At least one reader holds the lock.
+    pub const READER: Self = Self(READER as u8);
+
+    /// Returns the empty set of flags, i.e. an unlocked lock.
+    #[inline(always)]
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns the raw value of the flags.
+    #[inline(always)]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Returns `true` if no flags are set.
+    #[inline(always)]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if all of the flags in `other` are set.
+    #[inline(always)]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for RwLockFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// The reason [`RwLock::try_write_err`] failed to acquire the lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteBlocked {
+    /// The lock is held by this many readers.
+    Readers(usize),
+    /// The lock is held by an upgradeable guard.
+    Upgradable,
+    /// The lock is held by another writer.
+    Writer,
+}
+
+impl fmt::Display for WriteBlocked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WriteBlocked::Readers(n) => write!(f, "rwlock is held by {} reader(s)", n),
+            WriteBlocked::Upgradable => f.write_str("rwlock is held by an upgradeable guard"),
+            WriteBlocked::Writer => f.write_str("rwlock is held by a writer"),
+        }
+    }
+}
+
+/// The reason [`RwLockUpgradableGuard::try_upgrade_err`] failed to upgrade the guard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpgradeBlocked {
+    /// The lock is also held by this many readers.
+    Readers(usize),
+    /// The lock is in a state an upgradeable guard should never observe, such as held by a writer. This
+    /// indicates misuse of an unsafe API such as [`RwLock::force_unlock_upgradeable`].
+    InvalidState,
+}
+
+impl fmt::Display for UpgradeBlocked {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UpgradeBlocked::Readers(n) => write!(f, "rwlock is also held by {} reader(s)", n),
+            UpgradeBlocked::InvalidState => {
+                f.write_str("rwlock is in an invalid state for upgrading")
+            }
+        }
+    }
+}
+
 // Same unsafe impls as `std::sync::RwLock`
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
 
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: ?Sized + Send + Sync> Send for RwLockWriteGuard<'_, T> {}
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: ?Sized + Send + Sync> Sync for RwLockWriteGuard<'_, T> {}
 
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: ?Sized + Sync> Send for RwLockReadGuard<'_, T> {}
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: ?Sized + Sync> Sync for RwLockReadGuard<'_, T> {}
 
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: ?Sized + Send + Sync> Send for RwLockUpgradableGuard<'_, T> {}
+#[cfg(not(feature = "strict_send_sync"))]
 unsafe impl<T: ?Sized + Send + Sync> Sync for RwLockUpgradableGuard<'_, T> {}
 
+#[cfg(not(feature = "strict_send_sync"))]
+unsafe impl<T: ?Sized + Sync> Send for MappedRwLockReadGuard<'_, T> {}
+#[cfg(not(feature = "strict_send_sync"))]
+unsafe impl<T: ?Sized + Sync> Sync for MappedRwLockReadGuard<'_, T> {}
+
+#[cfg(not(feature = "strict_send_sync"))]
+unsafe impl<T: ?Sized + Send + Sync> Send for MappedRwLockWriteGuard<'_, T> {}
+#[cfg(not(feature = "strict_send_sync"))]
+unsafe impl<T: ?Sized + Send + Sync> Sync for MappedRwLockWriteGuard<'_, T> {}
+
 impl<T> RwLock<T> {
     /// Creates a new spinlock wrapping the supplied data.
     ///
     /// May be used statically:
     ///
     /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
     /// use nospin;
     ///
     /// static RW_LOCK: nospin::RwLock<()> = nospin::RwLock::new(());
@@ -215,15 +440,125 @@ impl<T> RwLock<T> {
     ///     // do something with lock
     ///     drop(lock);
     /// }
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
     /// ```
     #[inline]
     pub const fn new(data: T) -> Self {
         RwLock {
-            lock: NonAtomicUsize::new(0),
+            lock: NonAtomicState::new(0),
+            #[cfg(feature = "lock_order")]
+            lock_id: LockId::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Creates a new lock wrapping the supplied data that is already write locked.
+    ///
+    /// No guard corresponds to the held lock, so it can only be released with
+    /// [`RwLock::force_unlock_write`]. This is mostly useful for exercising the contended paths of
+    /// code, or for handing a pre-locked lock to FFI.
+    ///
+    /// As this is `const`, it can also initialize a `static` that must begin claimed, such as a
+    /// table owned by the boot code until its setup has completed.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// use nospin::RwLock;
+    ///
+    /// static ROUTES: RwLock<[u8; 4]> = RwLock::new_write_locked([0; 4]);
+    ///
+    /// fn early_boot() {
+    ///     // Nothing may read the table until it has been filled in
+    ///     unsafe { *ROUTES.as_mut_ptr() = [1, 2, 3, 4] };
+    ///     unsafe { ROUTES.force_unlock_write() };
+    /// }
+    ///
+    /// assert!(ROUTES.try_read().is_none());
+    /// early_boot();
+    /// assert_eq!(ROUTES.read()[3], 4);
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    #[inline]
+    pub const fn new_write_locked(data: T) -> Self {
+        RwLock {
+            lock: NonAtomicState::new(WRITER),
+            #[cfg(feature = "lock_order")]
+            lock_id: LockId::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Creates a new lock wrapping the supplied data that is already held for writing.
+    ///
+    /// This is the same as [`RwLock::new_write_locked`], under a name that reads better when a
+    /// `static` is claimed by the code that will set it up. The lock is released with
+    /// [`RwLock::force_unlock_write`].
+    ///
+    /// ```
+    /// use nospin::RwLock;
+    ///
+    /// let lock = RwLock::new_held_for_write(0);
+    /// assert!(lock.try_read().is_none());
+    ///
+    /// unsafe { lock.force_unlock_write() };
+    /// assert_eq!(*lock.read(), 0);
+    /// ```
+    #[inline]
+    pub const fn new_held_for_write(data: T) -> Self {
+        Self::new_write_locked(data)
+    }
+
+    /// Creates a new lock wrapping the supplied data that is already held by `count` readers.
+    ///
+    /// No guards correspond to the held reads, so they can only be released with
+    /// [`RwLock::force_unlock_read`], once per reader.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` exceeds the maximum number of readers the lock can track.
+    ///
+    /// ```
+    /// let lock = nospin::RwLock::new_read_locked(0, 2);
+    /// assert_eq!(lock.reader_count(), 2);
+    /// assert!(lock.try_write().is_none());
+    ///
+    /// unsafe {
+    ///     lock.force_unlock_read();
+    ///     lock.force_unlock_read();
+    /// }
+    /// assert!(lock.try_write().is_some());
+    /// ```
+    #[inline]
+    #[allow(clippy::unnecessary_cast)]
+    pub const fn new_read_locked(data: T, count: usize) -> Self {
+        assert!(count <= MAX_READERS as usize, "too many readers");
+        RwLock {
+            lock: NonAtomicState::new(count as State * READER),
+            #[cfg(feature = "lock_order")]
+            lock_id: LockId::new(),
             data: UnsafeCell::new(data),
         }
     }
 
+    /// Creates a new heap-allocated `RwLock` wrapping the supplied data.
+    ///
+    /// ```
+    /// use core::fmt::Display;
+    /// use nospin::RwLock;
+    ///
+    /// let lock: Box<RwLock<dyn Display>> = RwLock::boxed(42);
+    /// assert_eq!(lock.read().to_string(), "42");
+    /// ```
+    #[inline]
+    pub fn boxed(data: T) -> Box<Self> {
+        Box::new(Self::new(data))
+    }
+
     /// Consumes this `RwLock`, returning the underlying data.
     #[inline]
     pub fn into_inner(self) -> T {
@@ -232,6 +567,21 @@ impl<T> RwLock<T> {
         let RwLock { data, .. } = self;
         data.into_inner()
     }
+
+    /// Consumes this `RwLock`, transforming the underlying data with `f` into a new, unlocked `RwLock`.
+    ///
+    /// As the lock is consumed, no locking is needed to access the data.
+    ///
+    /// ```
+    /// let lock = nospin::RwLock::new(vec![1, 2, 3]);
+    /// let lock = lock.map(|v| v.len());
+    /// assert_eq!(*lock.read(), 3);
+    /// ```
+    #[inline]
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> RwLock<U> {
+        RwLock::new(f(self.into_inner()))
+    }
+
     /// Returns a mutable pointer to the underying data.
     ///
     /// This is mostly meant to be used for applications which require manual unlocking, but where
@@ -250,7 +600,7 @@ impl<T> RwLock<T> {
     ///     assert_eq!(lock.as_mut_ptr().read(), 42);
     ///     lock.as_mut_ptr().write(58);
     ///
-    ///     lock.force_write_unlock();
+    ///     lock.force_unlock_write();
     /// }
     ///
     /// assert_eq!(*lock.read(), 58);
@@ -262,6 +612,27 @@ impl<T> RwLock<T> {
     }
 }
 
+impl RwLock<()> {
+    /// Initialization constant of [`RwLock`].
+    ///
+    /// This is useful for building tables of locks in a `static`.
+    ///
+    /// ```
+    /// # #[cfg(not(feature = "strict_send_sync"))]
+    /// # fn main() {
+    /// static LOCKS: [nospin::RwLock<()>; 16] = [nospin::RwLock::INIT; 16];
+    ///
+    /// let guard = LOCKS[3].write();
+    /// assert!(LOCKS[3].try_read().is_none());
+    /// assert!(LOCKS[4].try_read().is_some());
+    /// # }
+    /// # #[cfg(feature = "strict_send_sync")]
+    /// # fn main() {}
+    /// ```
+    #[allow(clippy::declare_interior_mutable_const)]
+    pub const INIT: Self = Self::new(());
+}
+
 impl<T: ?Sized> RwLock<T> {
     /// Locks this rwlock with shared read access, panicking if it can be acquired.
     ///
@@ -270,6 +641,9 @@ impl<T: ?Sized> RwLock<T> {
     /// ordering of whether contentious readers or writers will acquire the lock
     /// first.
     ///
+    /// Unlike `spin`, this cannot actually spin: with a single thread, nobody else could release a held write
+    /// lock, so waiting for it would never end.
+    ///
     /// Returns an RAII guard which will release this thread's shared access
     /// once it is dropped.
     ///
@@ -283,7 +657,9 @@ impl<T: ?Sized> RwLock<T> {
     /// }
     /// ```
     #[inline]
-    pub fn read(&self) -> RwLockReadGuard<T> {
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        #[cfg(feature = "lock_order")]
+        lock_order::check(&self.lock_id);
         self.try_read()
             .expect("Failed to get read lock, who are you waiting for?")
     }
@@ -291,7 +667,8 @@ impl<T: ?Sized> RwLock<T> {
     /// Lock this rwlock with exclusive write access, panicking if it can be acquired.
     ///
     /// This function will not return while other writers or other readers
-    /// currently have access to the lock.
+    /// currently have access to the lock. As with [`RwLock::read`], it panics
+    /// rather than spinning should they do so.
     ///
     /// Returns an RAII guard which will drop the write access of this rwlock
     /// when dropped.
@@ -306,7 +683,9 @@ impl<T: ?Sized> RwLock<T> {
     /// }
     /// ```
     #[inline]
-    pub fn write(&self) -> RwLockWriteGuard<T> {
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        #[cfg(feature = "lock_order")]
+        lock_order::check(&self.lock_id);
         self.try_write()
             .expect("Failed to get read lock, who are you waiting for?")
     }
@@ -314,27 +693,123 @@ impl<T: ?Sized> RwLock<T> {
     /// Obtain a readable lock guard that can later be upgraded to a writable lock guard.
     /// Upgrades can be done through the [`RwLockUpgradableGuard::upgrade`](RwLockUpgradableGuard::upgrade) method.
     #[inline]
-    pub fn upgradeable_read(&self) -> RwLockUpgradableGuard<T> {
+    pub fn upgradeable_read(&self) -> RwLockUpgradableGuard<'_, T> {
+        #[cfg(feature = "lock_order")]
+        lock_order::check(&self.lock_id);
         self.try_upgradeable_read()
             .expect("Failed to get read lock, who are you waiting for?")
     }
-}
 
-impl<T: ?Sized> RwLock<T> {
-    // Acquire a read lock, returning the new lock value.
-    fn acquire_reader(&self) -> usize {
-        // An arbitrary cap that allows us to catch overflows long before they happen
-        const MAX_READERS: usize = usize::MAX / READER / 2;
+    /// Locks this rwlock with shared read access, applies `f` to the data and returns the result.
+    ///
+    /// The read guard is dropped before this method returns, so `f` is typically used to extract a
+    /// derived or cloned value from the data.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the lock cannot be acquired for reading.
+    ///
+    /// ```
+    /// let cache = nospin::RwLock::new(vec![1, 2, 3]);
+    ///
+    /// let len = cache.map_read(|c| c.len());
+    /// assert_eq!(len, 3);
+    /// assert!(cache.try_write().is_some());
+    /// ```
+    #[inline]
+    pub fn map_read<R, F: FnOnce(&T) -> R>(&self, f: F) -> R {
+        f(&self.read())
+    }
 
-        let value = self.lock.fetch_add(READER, Ordering::Acquire);
+    /// Locks this rwlock with exclusive write access, applies `f` to the data and returns the result.
+    ///
+    /// The write guard is dropped before this method returns.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the lock cannot be acquired for writing.
+    ///
+    /// ```
+    /// let cache = nospin::RwLock::new(vec![1, 2, 3]);
+    ///
+    /// let popped = cache.map_write(|c| c.pop());
+    /// assert_eq!(popped, Some(3));
+    /// assert_eq!(*cache.read(), [1, 2]);
+    /// ```
+    #[inline]
+    pub fn map_write<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+        f(&mut self.write())
+    }
 
-        if value > MAX_READERS * READER {
-            self.lock.fetch_sub(READER, Ordering::Relaxed);
-            panic!("Too many lock readers, cannot safely proceed");
-        } else {
-            value
+    /// Takes an upgradeable read of this rwlock and runs `read_fn`, upgrading to a write lock to run
+    /// `write_fn` only if `read_fn` returns [`ControlFlow::Continue`].
+    ///
+    /// This wraps the check-then-modify idiom of [`RwLock::upgradeable_read`] and
+    /// [`RwLockUpgradableGuard::try_upgrade`] in a single call. If `read_fn` returns
+    /// [`ControlFlow::Break`], its value is returned without ever taking the write lock.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if an upgradeable read cannot be acquired, or if the lock is still being read
+    /// elsewhere when it needs to be upgraded, for example by a read guard created within `read_fn` that is
+    /// still held.
+    ///
+    /// ```
+    /// use core::ops::ControlFlow;
+    ///
+    /// let cache = nospin::RwLock::new(vec![1, 2]);
+    ///
+    /// let push_if_missing = |value| {
+    ///     cache.read_then_maybe_write(
+    ///         |c| if c.contains(&value) { ControlFlow::Break(false) } else { ControlFlow::Continue(()) },
+    ///         |c| {
+    ///             c.push(value);
+    ///             true
+    ///         },
+    ///     )
+    /// };
+    ///
+    /// assert!(!push_if_missing(2));
+    /// assert!(push_if_missing(3));
+    /// assert_eq!(*cache.read(), [1, 2, 3]);
+    /// ```
+    pub fn read_then_maybe_write<R, F, W>(&self, read_fn: F, write_fn: W) -> R
+    where
+        F: FnOnce(&T) -> ControlFlow<R, ()>,
+        W: FnOnce(&mut T) -> R,
+    {
+        let upgradeable = self.upgradeable_read();
+        if let ControlFlow::Break(result) = read_fn(&upgradeable) {
+            return result;
+        }
+        match upgradeable.try_upgrade() {
+            Ok(mut writable) => write_fn(&mut writable),
+            Err(_) => panic!("Failed to upgrade read lock, who are you waiting for?"),
         }
     }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// The maximum number of readers that can hold the lock at once.
+    ///
+    /// Acquiring another reader beyond this panics, or with the `saturating_readers` feature leaves the reader
+    /// uncounted. This depends on the width of the lock state: it is `usize::MAX / 8`, or 8191 with the
+    /// `small_state` feature.
+    ///
+    /// ```
+    /// use nospin::RwLock;
+    ///
+    /// let lock = RwLock::new_read_locked((), RwLock::<()>::MAX_READERS);
+    ///
+    /// // Either panics, or with `saturating_readers` leaves the reader uncounted
+    /// let readers = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    ///     let _extra = lock.read();
+    ///     lock.reader_count()
+    /// }));
+    /// assert!(readers.map_or(true, |readers| readers == RwLock::<()>::MAX_READERS));
+    /// ```
+    #[allow(clippy::unnecessary_cast)]
+    pub const MAX_READERS: usize = MAX_READERS as usize;
 
     /// Attempt to acquire this lock with shared read access.
     ///
@@ -359,20 +834,74 @@ impl<T: ?Sized> RwLock<T> {
     /// }
     /// ```
     #[inline]
-    pub fn try_read(&self) -> Option<RwLockReadGuard<T>> {
-        let value = self.acquire_reader();
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        let (value, counted) = self.lock.acquire_reader();
 
         // We check the UPGRADED bit here so that new readers are prevented when an UPGRADED lock is held.
         // This helps reduce writer starvation.
         if value & (WRITER | UPGRADED) != 0 {
             // Lock is taken, undo.
-            self.lock.fetch_sub(READER, Ordering::Release);
+            if counted {
+                self.lock.fetch_sub(READER, Ordering::Release);
+            }
             None
         } else {
-            Some(RwLockReadGuard {
-                lock: &self.lock,
-                data: unsafe { &*self.data.get() },
-            })
+            #[cfg(feature = "lock_order")]
+            lock_order::acquired(&self.lock, &self.lock_id);
+            Some(RwLockReadGuard::new(
+                &self.lock,
+                unsafe { &*self.data.get() },
+                counted,
+            ))
+        }
+    }
+
+    /// Attempt to acquire this lock with shared read access.
+    ///
+    /// Unlike [`RwLock::try_read`], this function is allowed to spuriously fail even when acquiring shared read access
+    /// would otherwise succeed, which can result in more efficient code on some platforms.
+    #[inline]
+    pub fn try_read_weak(&self) -> Option<RwLockReadGuard<'_, T>> {
+        self.try_read()
+    }
+
+    /// Attempt to acquire this lock with shared read access, even if an upgradeable read is currently held.
+    ///
+    /// Unlike [`RwLock::try_read`], this only fails if the lock is write locked. [`RwLock::try_read`] refuses new
+    /// readers while an upgradeable guard is held to avoid starving the upgrade, but code that already holds a read
+    /// and re-enters the lock needs its nested read to succeed regardless. Note that any read acquired this way
+    /// prevents a held upgradeable guard from upgrading until it is dropped.
+    ///
+    /// ```
+    /// let mylock = nospin::RwLock::new(0);
+    /// let upgradeable = mylock.upgradeable_read();
+    ///
+    /// assert!(mylock.try_read().is_none());
+    /// let reader = mylock.try_read_recursive().unwrap();
+    /// assert_eq!(*reader, 0);
+    ///
+    /// let upgradeable = upgradeable.try_upgrade().unwrap_err();
+    /// drop(reader);
+    /// assert!(upgradeable.try_upgrade().is_ok());
+    /// ```
+    #[inline]
+    pub fn try_read_recursive(&self) -> Option<RwLockReadGuard<'_, T>> {
+        let (value, counted) = self.lock.acquire_reader();
+
+        if value & WRITER != 0 {
+            // Lock is taken, undo.
+            if counted {
+                self.lock.fetch_sub(READER, Ordering::Release);
+            }
+            None
+        } else {
+            #[cfg(feature = "lock_order")]
+            lock_order::acquired(&self.lock, &self.lock_id);
+            Some(RwLockReadGuard::new(
+                &self.lock,
+                unsafe { &*self.data.get() },
+                counted,
+            ))
         }
     }
 
@@ -383,60 +912,224 @@ impl<T: ?Sized> RwLock<T> {
     /// This function provides no synchronization guarantees and so its result should be considered 'out of date'
     /// the instant it is called. Do not use it for synchronization purposes. However, it may be useful as a heuristic.
     pub fn reader_count(&self) -> usize {
-        let state = self.lock.load(Ordering::Relaxed);
-        state / READER + (state & UPGRADED) / UPGRADED
+        let state = self.state_snapshot();
+        state.readers + state.has_upgradable as usize
     }
 
-    /// Return the number of writers that currently hold the lock.
+    /// Return the number of plain shared readers that currently hold the lock, excluding an upgradable reader.
     ///
-    /// Because [`RwLock`] guarantees exclusive mutable access, this function may only return either `0` or `1`.
+    /// Whether an upgradable reader holds the lock can be checked separately with [`RwLock::state_snapshot`].
     ///
     /// # Safety
     ///
     /// This function provides no synchronization guarantees and so its result should be considered 'out of date'
     /// the instant it is called. Do not use it for synchronization purposes. However, it may be useful as a heuristic.
-    pub fn writer_count(&self) -> usize {
-        (self.lock.load(Ordering::Relaxed) & WRITER) / WRITER
+    ///
+    /// ```
+    /// let lock = nospin::RwLock::new(());
+    ///
+    /// let _r = lock.read();
+    /// let _u = lock.upgradeable_read();
+    /// assert_eq!(lock.shared_reader_count(), 1);
+    /// assert_eq!(lock.reader_count(), 2);
+    /// ```
+    pub fn shared_reader_count(&self) -> usize {
+        self.state_snapshot().readers
     }
 
-    /// Force decrement the reader count.
+    /// Return the number of writers that currently hold the lock.
+    ///
+    /// Because [`RwLock`] guarantees exclusive mutable access, this function may only return either `0` or `1`.
     ///
     /// # Safety
     ///
-    /// This is *extremely* unsafe if there are outstanding `RwLockReadGuard`s
-    /// live, or if called more times than `read` has been called, but can be
-    /// useful in FFI contexts where the caller doesn't know how to deal with
-    /// RAII. The underlying atomic operation uses `Ordering::Release`.
-    #[inline]
-    pub unsafe fn force_read_decrement(&self) {
-        debug_assert!(self.lock.load(Ordering::Relaxed) & !WRITER > 0);
-        self.lock.fetch_sub(READER, Ordering::Release);
+    /// This function provides no synchronization guarantees and so its result should be considered 'out of date'
+    /// the instant it is called. Do not use it for synchronization purposes. However, it may be useful as a heuristic.
+    pub fn writer_count(&self) -> usize {
+        self.state_snapshot().has_writer as usize
     }
 
-    /// Force unlock exclusive write access.
+    /// Decodes the current state of the lock from a single load of its state word.
+    ///
+    /// Unlike combining [`RwLock::reader_count`] and [`RwLock::writer_count`], the fields of the returned
+    /// [`RwLockState`] are always consistent with one another.
     ///
     /// # Safety
     ///
-    /// This is *extremely* unsafe if there are outstanding `RwLockWriteGuard`s
-    /// live, or if called when there are current readers, but can be useful in
-    /// FFI contexts where the caller doesn't know how to deal with RAII. The
-    /// underlying atomic operation uses `Ordering::Release`.
-    #[inline]
-    pub unsafe fn force_write_unlock(&self) {
-        debug_assert_eq!(self.lock.load(Ordering::Relaxed) & !(WRITER | UPGRADED), 0);
-        self.lock.fetch_and(!(WRITER | UPGRADED), Ordering::Release);
+    /// This function provides no synchronization guarantees and so its result should be considered 'out of date'
+    /// the instant it is called. Do not use it for synchronization purposes. However, it may be useful as a heuristic.
+    ///
+    /// ```
+    /// let lock = nospin::RwLock::new(0);
+    ///
+    /// let _r = lock.read();
+    /// let _u = lock.upgradeable_read();
+    ///
+    /// let state = lock.state_snapshot();
+    /// assert_eq!(state.readers, 1);
+    /// assert!(state.has_upgradable);
+    /// assert!(!state.has_writer);
+    /// ```
+    #[allow(clippy::unnecessary_cast)]
+    pub fn state_snapshot(&self) -> RwLockState {
+        let state = self.lock.load(Ordering::Relaxed);
+        RwLockState {
+            readers: ((state & !SATURATED) / READER) as usize,
+            has_writer: state & WRITER != 0,
+            has_upgradable: state & UPGRADED != 0,
+        }
     }
 
-    /// Attempt to lock this rwlock with exclusive write access.
+    /// Panics if the lock is currently held in any way.
     ///
-    /// This function does not ever block, and it will return `None` if a call
-    /// to `write` would otherwise block. If successful, an RAII guard is
-    /// returned.
+    /// This is intended for tests, to check that code released every guard it took without acquiring the lock
+    /// itself as `assert!(lock.try_write().is_some())` would.
     ///
+    /// ```should_panic
+    /// let lock = nospin::RwLock::new(0);
+    /// lock.assert_unlocked();
+    ///
+    /// let _r = lock.read();
+    /// lock.assert_unlocked();
     /// ```
-    /// let mylock = nospin::RwLock::new(0);
-    /// {
-    ///     match mylock.try_write() {
+    pub fn assert_unlocked(&self) {
+        let state = self.state_snapshot();
+        if state.has_writer {
+            panic!("RwLock is unexpectedly write locked");
+        }
+        if state.readers != 0 || state.has_upgradable {
+            panic!(
+                "RwLock is unexpectedly read locked by {} reader(s){}",
+                state.readers,
+                if state.has_upgradable {
+                    " and an upgradeable reader"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+
+    /// Returns the set of ways the lock is currently held.
+    ///
+    /// This decodes the state word the same way as [`RwLock::state_snapshot`], but as a set of
+    /// [`RwLockFlags`] for concise assertions.
+    ///
+    /// # Safety
+    ///
+    /// This function provides no synchronization guarantees and so its result should be considered 'out of date'
+    /// the instant it is called. Do not use it for synchronization purposes. However, it may be useful as a heuristic.
+    pub fn flags(&self) -> RwLockFlags {
+        let state = self.state_snapshot();
+        let mut flags = RwLockFlags::empty();
+        if state.has_writer {
+            flags = flags | RwLockFlags::WRITER;
+        }
+        if state.has_upgradable {
+            flags = flags | RwLockFlags::UPGRADED;
+        }
+        if state.readers > 0 {
+            flags = flags | RwLockFlags::READER;
+        }
+        flags
+    }
+
+    /// Force decrement the reader count, releasing one reader.
+    ///
+    /// # Safety
+    ///
+    /// This is *extremely* unsafe if there are outstanding `RwLockReadGuard`s
+    /// live, or if called more times than `read` has been called, but can be
+    /// useful in FFI contexts where the caller doesn't know how to deal with
+    /// RAII. The underlying atomic operation uses `Ordering::Release`.
+    #[inline]
+    pub unsafe fn force_unlock_read(&self) {
+        debug_check!(self.lock.load(Ordering::Relaxed) & !WRITER > 0);
+        #[cfg(feature = "lock_order")]
+        lock_order::released(&self.lock);
+        self.lock.fetch_sub(READER, Ordering::Release);
+    }
+
+    /// Force unlock exclusive write access.
+    ///
+    /// Like dropping a [`RwLockWriteGuard`], this clears both the writer bit and any `UPGRADED` bit. While the
+    /// lock is write locked, an `UPGRADED` bit can only have been left by a failed attempt to take an upgradeable
+    /// read, which leaves it for the writer to clear, as no upgradeable guard can coexist with a writer. Leaving
+    /// it set would prevent the lock from ever being written or upgradeably read again. An upgradeable guard that
+    /// was upgraded to the write lock being released gives up its `UPGRADED` bit when upgrading, so it is not
+    /// affected either.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be write locked, with the write guard (if any) forgotten so that it will not unlock
+    /// again. This is *extremely* unsafe if there are outstanding `RwLockWriteGuard`s
+    /// live, or if called when there are current readers, but can be useful in
+    /// FFI contexts where the caller doesn't know how to deal with RAII. Calling this
+    /// while the lock is not write locked would release any upgradeable guard
+    /// currently held. The underlying atomic operation uses `Ordering::Release`.
+    #[inline]
+    pub unsafe fn force_unlock_write(&self) {
+        debug_check!(
+            self.lock.load(Ordering::Relaxed) & WRITER != 0,
+            "Attempted to force unlock write on an RwLock that is not write locked"
+        );
+        debug_check_eq!(self.lock.load(Ordering::Relaxed) & !(WRITER | UPGRADED), 0);
+        #[cfg(feature = "lock_order")]
+        lock_order::released(&self.lock);
+        self.lock.fetch_and(!(WRITER | UPGRADED), Ordering::Release);
+    }
+
+    /// Force unlock upgradeable read access.
+    ///
+    /// # Safety
+    ///
+    /// This is *extremely* unsafe if there are outstanding `RwLockUpgradableGuard`s
+    /// live, or if called when no upgradeable lock is held, but can be useful in
+    /// FFI contexts where the caller doesn't know how to deal with RAII. The
+    /// underlying atomic operation uses `Ordering::Release`.
+    #[inline]
+    pub unsafe fn force_unlock_upgradeable(&self) {
+        debug_check_eq!(
+            self.lock.load(Ordering::Relaxed) & (WRITER | UPGRADED),
+            UPGRADED
+        );
+        #[cfg(feature = "lock_order")]
+        lock_order::released(&self.lock);
+        self.lock.fetch_sub(UPGRADED, Ordering::Release);
+    }
+
+    /// Force decrement the reader count.
+    ///
+    /// # Safety
+    ///
+    /// See [`RwLock::force_unlock_read`].
+    #[inline]
+    #[deprecated(note = "renamed to `force_unlock_read`")]
+    pub unsafe fn force_read_decrement(&self) {
+        unsafe { self.force_unlock_read() }
+    }
+
+    /// Force unlock exclusive write access.
+    ///
+    /// # Safety
+    ///
+    /// See [`RwLock::force_unlock_write`].
+    #[inline]
+    #[deprecated(note = "renamed to `force_unlock_write`")]
+    pub unsafe fn force_write_unlock(&self) {
+        unsafe { self.force_unlock_write() }
+    }
+
+    /// Attempt to lock this rwlock with exclusive write access.
+    ///
+    /// This function does not ever block, and it will return `None` if a call
+    /// to `write` would otherwise block. If successful, an RAII guard is
+    /// returned.
+    ///
+    /// ```
+    /// let mylock = nospin::RwLock::new(0);
+    /// {
+    ///     match mylock.try_write() {
     ///         Some(mut data) => {
     ///             // The lock is now locked and the data can be written
     ///             *data += 1;
@@ -447,12 +1140,14 @@ impl<T: ?Sized> RwLock<T> {
     /// }
     /// ```
     #[inline]
-    pub fn try_write(&self) -> Option<RwLockWriteGuard<T>> {
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
         if self
             .lock
             .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
             .is_ok()
         {
+            #[cfg(feature = "lock_order")]
+            lock_order::acquired(&self.lock, &self.lock_id);
             Some(RwLockWriteGuard {
                 inner: self,
                 data: unsafe { &mut *self.data.get() },
@@ -462,19 +1157,59 @@ impl<T: ?Sized> RwLock<T> {
         }
     }
 
+    /// Attempt to lock this rwlock with exclusive write access, reporting why it could not be acquired
+    /// on failure.
+    ///
+    /// This behaves like [`RwLock::try_write`], but decodes the lock state into a [`WriteBlocked`] rather
+    /// than returning `None`. A writer takes precedence over an upgradeable guard, which in turn takes
+    /// precedence over plain readers.
+    ///
+    /// ```
+    /// use nospin::rwlock::WriteBlocked;
+    ///
+    /// let mylock = nospin::RwLock::new(0);
+    ///
+    /// let r1 = mylock.read();
+    /// let r2 = mylock.read();
+    /// assert_eq!(mylock.try_write_err().err(), Some(WriteBlocked::Readers(2)));
+    /// drop((r1, r2));
+    ///
+    /// let upgradeable = mylock.upgradeable_read();
+    /// assert_eq!(mylock.try_write_err().err(), Some(WriteBlocked::Upgradable));
+    /// drop(upgradeable);
+    ///
+    /// *mylock.try_write_err().unwrap() += 1;
+    /// assert_eq!(*mylock.read(), 1);
+    /// ```
+    #[inline]
+    pub fn try_write_err(&self) -> Result<RwLockWriteGuard<'_, T>, WriteBlocked> {
+        self.try_write().ok_or_else(|| {
+            let state = self.state_snapshot();
+            if state.has_writer {
+                WriteBlocked::Writer
+            } else if state.has_upgradable {
+                WriteBlocked::Upgradable
+            } else {
+                WriteBlocked::Readers(state.readers)
+            }
+        })
+    }
+
     /// Attempt to lock this rwlock with exclusive write access.
     ///
     /// Unlike [`RwLock::try_write`], this function is allowed to spuriously fail even when acquiring exclusive write access
     /// would otherwise succeed, which can result in more efficient code on some platforms.
     #[inline]
-    pub fn try_write_weak(&self) -> Option<RwLockWriteGuard<T>> {
+    pub fn try_write_weak(&self) -> Option<RwLockWriteGuard<'_, T>> {
         self.try_write()
     }
 
     /// Tries to obtain an upgradeable lock guard.
     #[inline]
-    pub fn try_upgradeable_read(&self) -> Option<RwLockUpgradableGuard<T>> {
+    pub fn try_upgradeable_read(&self) -> Option<RwLockUpgradableGuard<'_, T>> {
         if self.lock.fetch_or(UPGRADED, Ordering::Acquire) & (WRITER | UPGRADED) == 0 {
+            #[cfg(feature = "lock_order")]
+            lock_order::acquired(&self.lock, &self.lock_id);
             Some(RwLockUpgradableGuard {
                 inner: self,
                 data: unsafe { &*self.data.get() },
@@ -486,6 +1221,15 @@ impl<T: ?Sized> RwLock<T> {
         }
     }
 
+    /// Tries to obtain an upgradeable lock guard.
+    ///
+    /// Unlike [`RwLock::try_upgradeable_read`], this function is allowed to spuriously fail even when acquiring
+    /// an upgradeable lock would otherwise succeed, which can result in more efficient code on some platforms.
+    #[inline]
+    pub fn try_upgradeable_read_weak(&self) -> Option<RwLockUpgradableGuard<'_, T>> {
+        self.try_upgradeable_read()
+    }
+
     /// Returns a mutable reference to the underlying data.
     ///
     /// Since this call borrows the `RwLock` mutably, no actual locking needs to
@@ -506,7 +1250,24 @@ impl<T: ?Sized> RwLock<T> {
 }
 
 impl<T: ?Sized + fmt::Debug> fmt::Debug for RwLock<T> {
+    /// Formats the data of the lock if it can be read.
+    ///
+    /// The alternate format (`{:#?}`) additionally shows who holds the lock, which helps to diagnose a stuck lock.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            // Taken before reading the data, so our own read guard is not counted
+            let state = self.state_snapshot();
+            let mut d = f.debug_struct("RwLock");
+            d.field("readers", &state.readers)
+                .field("writer", &state.has_writer)
+                .field("upgradable", &state.has_upgradable);
+            match self.try_read() {
+                Some(guard) => d.field("data", &&*guard),
+                None => d.field("data", &format_args!("<locked>")),
+            };
+            return d.finish();
+        }
+
         match self.try_read() {
             Some(guard) => write!(f, "RwLock {{ data: ")
                 .and_then(|()| (*guard).fmt(f))
@@ -516,6 +1277,62 @@ impl<T: ?Sized + fmt::Debug> fmt::Debug for RwLock<T> {
     }
 }
 
+impl<T: ?Sized> fmt::Pointer for RwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.data.get(), f)
+    }
+}
+
+// Read locks may be shared, so comparing a lock with itself simply takes a single guard for both sides.
+impl<T: ?Sized> RwLock<T> {
+    fn with_pair<R>(&self, other: &Self, f: impl FnOnce(&T, &T) -> R) -> R {
+        let this = self
+            .try_read()
+            .expect("Failed to get read lock for comparison");
+        if core::ptr::eq(self, other) {
+            return f(&this, &this);
+        }
+        f(
+            &this,
+            &other
+                .try_read()
+                .expect("Failed to get read lock for comparison"),
+        )
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for RwLock<T> {
+    /// Compares the data of two locks.
+    ///
+    /// This acquires a read lock on both locks for the duration of the comparison, and so will panic if either is
+    /// write locked or held by an upgradeable guard.
+    fn eq(&self, other: &Self) -> bool {
+        self.with_pair(other, |a, b| a == b)
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for RwLock<T> {}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for RwLock<T> {
+    /// Compares the data of two locks.
+    ///
+    /// This acquires a read lock on both locks for the duration of the comparison, and so will panic if either is
+    /// write locked or held by an upgradeable guard.
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.with_pair(other, |a, b| a.partial_cmp(b))
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for RwLock<T> {
+    /// Compares the data of two locks.
+    ///
+    /// This acquires a read lock on both locks for the duration of the comparison, and so will panic if either is
+    /// write locked or held by an upgradeable guard.
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.with_pair(other, |a, b| a.cmp(b))
+    }
+}
+
 impl<T: Default> Default for RwLock<T> {
     fn default() -> Self {
         Self::new(Default::default())
@@ -528,7 +1345,171 @@ impl<T> From<T> for RwLock<T> {
     }
 }
 
+/// Moves the data out of a [`Mutex`](crate::Mutex) into a new, unlocked `RwLock`.
+///
+/// Because an `RwLock<Mutex<T>>` can also be built from a `Mutex<T>` via `From<T>`, the target type
+/// usually needs to be spelled out.
+///
+/// ```
+/// use nospin::{Mutex, RwLock};
+///
+/// let lock: RwLock<u32> = Mutex::new(1).into();
+/// assert_eq!(*lock.read(), 1);
+/// ```
+#[cfg(feature = "mutex")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mutex")))]
+impl<T> From<crate::Mutex<T>> for RwLock<T> {
+    fn from(mutex: crate::Mutex<T>) -> Self {
+        Self::new(mutex.into_inner())
+    }
+}
+
+/// Moves the data out of an `RwLock` into a new, unlocked [`Mutex`](crate::Mutex).
+///
+/// As with the conversion in the other direction, the target type usually needs to be spelled out.
+///
+/// ```
+/// use nospin::{Mutex, RwLock};
+///
+/// let lock: Mutex<u32> = RwLock::new(1).into();
+/// assert_eq!(*lock.lock(), 1);
+/// ```
+#[cfg(feature = "mutex")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mutex")))]
+impl<T> From<RwLock<T>> for crate::Mutex<T> {
+    fn from(rwlock: RwLock<T>) -> Self {
+        Self::new(rwlock.into_inner())
+    }
+}
+
+impl<C: IntoIterator> IntoIterator for RwLock<C> {
+    type Item = C::Item;
+    type IntoIter = C::IntoIter;
+
+    /// Consumes the lock and iterates over the inner collection by value.
+    ///
+    /// As the lock is consumed, no locking is needed.
+    fn into_iter(self) -> C::IntoIter {
+        self.into_inner().into_iter()
+    }
+}
+
 impl<'rwlock, T: ?Sized> RwLockReadGuard<'rwlock, T> {
+    // Creates a guard for a reader acquired with `NonAtomicState::acquire_reader`.
+    #[inline(always)]
+    fn new(lock: &'rwlock NonAtomicState, data: *const T, counted: bool) -> Self {
+        if !counted {
+            lock.fetch_or(SATURATED, Ordering::Relaxed);
+        }
+        RwLockReadGuard {
+            lock,
+            data,
+            #[cfg(feature = "saturating_readers")]
+            counted,
+        }
+    }
+
+    /// Returns a reference to the locked data.
+    ///
+    /// This is equivalent to dereferencing the guard. It is an associated function that needs to be used as
+    /// `RwLockReadGuard::get(...)`, so as not to conflict with a method of the same name on the locked data.
+    #[inline(always)]
+    pub fn get(this: &Self) -> &T {
+        unsafe { &*this.data }
+    }
+
+    /// Returns a non-null pointer to the locked data, such as for handing it to C.
+    ///
+    /// The pointer is only valid while the guard is alive, and must not be written through. It is an associated
+    /// function that needs to be used as `RwLockReadGuard::as_non_null(...)`, so as not to conflict with a method
+    /// of the same name on the locked data.
+    #[inline(always)]
+    pub fn as_non_null(this: &Self) -> NonNull<T> {
+        unsafe { NonNull::new_unchecked(this.data as *mut T) }
+    }
+
+    /// Acquires another read of the same lock, returning a second guard independent of this one.
+    ///
+    /// This increments the reader count of the lock just like [`RwLock::read`], and each guard releases its own
+    /// read when dropped. Since this guard already holds a read, the new read is granted even if an upgradeable
+    /// read has since been requested. It is an associated function that needs to be used as
+    /// `RwLockReadGuard::clone_guard(...)`, so as not to conflict with a method of the same name on the locked data.
+    ///
+    /// ```
+    /// use nospin::{RwLock, RwLockReadGuard};
+    ///
+    /// let mylock = RwLock::new(0);
+    /// let first = mylock.read();
+    /// let second = RwLockReadGuard::clone_guard(&first);
+    /// assert_eq!(mylock.reader_count(), 2);
+    ///
+    /// drop(first);
+    /// assert_eq!(*second, 0);
+    /// assert_eq!(mylock.reader_count(), 1);
+    /// ```
+    #[inline]
+    pub fn clone_guard(this: &Self) -> Self {
+        let (_, counted) = this.lock.acquire_reader();
+        #[cfg(feature = "lock_order")]
+        lock_order::reacquired(this.lock);
+        Self::new(this.lock, this.data, counted)
+    }
+
+    /// Releases the read lock while running `f`, then acquires it again for a new guard.
+    ///
+    /// While `f` runs, the lock may be written, for example by re-entrant code that only has access to the
+    /// [`RwLock`] itself, so the data seen through the returned guard may have been mutated.
+    ///
+    /// The guard is consumed, so the data cannot be reached through it while the lock is released. Since there is
+    /// no one else to wait for in single-threaded code, if the lock is still write locked when `f` returns (for
+    /// example because a write guard was leaked), the read is not taken back and the result of `f` is returned
+    /// as `Err`. Should `f` panic, the lock is left released.
+    ///
+    /// ```
+    /// use nospin::{RwLock, RwLockReadGuard};
+    ///
+    /// let lock = RwLock::new(1);
+    /// let guard = lock.read();
+    ///
+    /// let ((), guard) = RwLockReadGuard::unlocked(guard, || *lock.write() += 1).unwrap();
+    /// assert_eq!(*guard, 2);
+    ///
+    /// let leaked = RwLockReadGuard::unlocked(guard, || core::mem::forget(lock.write()));
+    /// assert!(leaked.is_err());
+    /// ```
+    pub fn unlocked<R, F: FnOnce() -> R>(this: Self, f: F) -> Result<(R, Self), R> {
+        // The guard cannot record the lock again without the `RwLock`, so its record is kept while `f` runs and
+        // only dropped if the read is not taken back
+        #[cfg(feature = "lock_order")]
+        struct Record<'a>(&'a NonAtomicState);
+
+        #[cfg(feature = "lock_order")]
+        impl Drop for Record<'_> {
+            fn drop(&mut self) {
+                lock_order::released(self.0);
+            }
+        }
+
+        let lock = this.lock;
+        let data = this.data;
+        #[cfg(feature = "lock_order")]
+        let record = {
+            lock_order::reacquired(lock);
+            Record(lock)
+        };
+        drop(this);
+
+        let result = f();
+
+        if lock.load(Ordering::Relaxed) & WRITER != 0 {
+            return Err(result);
+        }
+        let (_, counted) = lock.acquire_reader();
+        #[cfg(feature = "lock_order")]
+        forget(record);
+        Ok((result, Self::new(lock, data, counted)))
+    }
+
     /// Leak the lock guard, yielding a reference to the underlying data.
     ///
     /// Note that this function will permanently lock the original lock for all but reading locks.
@@ -546,11 +1527,61 @@ impl<'rwlock, T: ?Sized> RwLockReadGuard<'rwlock, T> {
         // Safety: We know statically that only we are referencing data
         unsafe { &*this.data }
     }
+
+    /// Makes a new [`MappedRwLockReadGuard`] for a component of the locked data.
+    ///
+    /// This is an associated function that needs to be used as `RwLockReadGuard::map(...)`, so as not to
+    /// conflict with a method of the same name on the locked data.
+    ///
+    /// ```
+    /// let mylock = nospin::RwLock::new((1, 2));
+    ///
+    /// let second = nospin::RwLockReadGuard::map(mylock.read(), |pair| &pair.1);
+    /// assert_eq!(*second, 2);
+    /// ```
+    #[inline]
+    pub fn map<U: ?Sized, F: FnOnce(&T) -> &U>(
+        this: Self,
+        f: F,
+    ) -> MappedRwLockReadGuard<'rwlock, U> {
+        let data: *const U = f(unsafe { &*this.data });
+        let this = ManuallyDrop::new(this);
+        MappedRwLockReadGuard {
+            lock: this.lock,
+            data,
+            #[cfg(feature = "saturating_readers")]
+            counted: this.counted,
+        }
+    }
+
+    /// Clones a component of the locked data, selected by `f`, while the read lock is held.
+    ///
+    /// This snapshots part of the data without the guard returned by [`RwLockReadGuard::map`]. It is an
+    /// associated function that needs to be used as `RwLockReadGuard::map_clone(...)`, so as not to conflict
+    /// with a method of the same name on the locked data.
+    ///
+    /// ```
+    /// use nospin::{RwLock, RwLockReadGuard};
+    ///
+    /// let lock = RwLock::new((1, String::from("name")));
+    ///
+    /// let name = RwLockReadGuard::map_clone(&lock.read(), |pair| &pair.1);
+    /// lock.write().1.push('s');
+    /// assert_eq!(name, "name");
+    /// ```
+    #[inline]
+    pub fn map_clone<U: Clone, F: FnOnce(&T) -> &U>(this: &Self, f: F) -> U {
+        f(unsafe { &*this.data }).clone()
+    }
 }
 
 impl<T: ?Sized + fmt::Debug> fmt::Debug for RwLockReadGuard<'_, T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&**self, f)
+        if cfg!(feature = "verbose_debug") {
+            f.debug_tuple("RwLockReadGuard").field(&&**self).finish()
+        } else {
+            fmt::Debug::fmt(&**self, f)
+        }
     }
 }
 
@@ -560,6 +1591,36 @@ impl<T: ?Sized + fmt::Display> fmt::Display for RwLockReadGuard<'_, T> {
     }
 }
 
+impl<T: ?Sized> fmt::Pointer for RwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.data, f)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq<T> for RwLockReadGuard<'_, T> {
+    fn eq(&self, other: &T) -> bool {
+        **self == *other
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for RwLockReadGuard<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd<T> for RwLockReadGuard<'_, T> {
+    fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(other)
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for RwLockReadGuard<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
 impl<'rwlock, T: ?Sized + fmt::Debug> RwLockUpgradableGuard<'rwlock, T> {
     /// Upgrades an upgradeable lock guard to a writable lock guard.
     ///
@@ -577,6 +1638,50 @@ impl<'rwlock, T: ?Sized + fmt::Debug> RwLockUpgradableGuard<'rwlock, T> {
 }
 
 impl<'rwlock, T: ?Sized> RwLockUpgradableGuard<'rwlock, T> {
+    /// Returns a reference to the locked data.
+    ///
+    /// This is equivalent to dereferencing the guard. It is an associated function that needs to be used as
+    /// `RwLockUpgradableGuard::get(...)`, so as not to conflict with a method of the same name on the locked data.
+    #[inline(always)]
+    pub fn get(this: &Self) -> &T {
+        unsafe { &*this.data }
+    }
+
+    /// Tries to upgrade an upgradeable lock guard to a writable lock guard, reporting why it could not be
+    /// upgraded on failure.
+    ///
+    /// This behaves like [`RwLockUpgradableGuard::try_upgrade`], but returns an [`UpgradeBlocked`] alongside the
+    /// guard on failure. Upgradeable guards may coexist with plain readers, so these are the usual reason, and
+    /// knowing how many remain helps to decide whether to release them or give up.
+    ///
+    /// ```
+    /// use nospin::rwlock::UpgradeBlocked;
+    ///
+    /// let mylock = nospin::RwLock::new(0);
+    /// let reader = mylock.read();
+    /// let upgradeable = mylock.upgradeable_read();
+    ///
+    /// let upgradeable = match upgradeable.try_upgrade_err() {
+    ///     Err((guard, UpgradeBlocked::Readers(1))) => guard,
+    ///     _ => unreachable!(),
+    /// };
+    ///
+    /// drop(reader);
+    /// *upgradeable.try_upgrade_err().unwrap() += 1;
+    /// ```
+    #[inline]
+    pub fn try_upgrade_err(self) -> Result<RwLockWriteGuard<'rwlock, T>, (Self, UpgradeBlocked)> {
+        self.try_upgrade().map_err(|this| {
+            let state = this.inner.state_snapshot();
+            let reason = if state.has_writer || !state.has_upgradable {
+                UpgradeBlocked::InvalidState
+            } else {
+                UpgradeBlocked::Readers(state.readers)
+            };
+            (this, reason)
+        })
+    }
+
     /// Tries to upgrade an upgradeable lock guard to a writable lock guard.
     ///
     /// ```
@@ -620,33 +1725,72 @@ impl<'rwlock, T: ?Sized> RwLockUpgradableGuard<'rwlock, T> {
         self.try_upgrade()
     }
 
-    #[inline]
-    /// Downgrades the upgradeable lock guard to a readable, shared lock guard. Cannot fail and is guaranteed not to spin.
+    /// Tries to temporarily upgrade the upgradeable lock guard to a writable lock guard, running `f` with mutable
+    /// access to the data. Returns `None` without calling `f` if existing readers prevent the upgrade.
+    ///
+    /// Once `f` returns (or panics) the lock is returned to the upgradeable state held by this guard.
     ///
     /// ```
-    /// let mylock = nospin::RwLock::new(1);
+    /// let mylock = nospin::RwLock::new(0);
     ///
-    /// let upgradeable = mylock.upgradeable_read();
-    /// assert!(mylock.try_read().is_none());
-    /// assert_eq!(*upgradeable, 1);
+    /// let reader = mylock.read();
+    /// let mut upgradeable = mylock.upgradeable_read();
+    /// assert_eq!(upgradeable.try_with_upgraded(|data| *data += 1), None);
     ///
-    /// let readable = upgradeable.downgrade(); // This is guaranteed not to spin
-    /// assert!(mylock.try_read().is_some());
-    /// assert_eq!(*readable, 1);
+    /// drop(reader);
+    /// assert_eq!(upgradeable.try_with_upgraded(|data| *data += 1), Some(()));
+    /// assert_eq!(*upgradeable, 1);
     /// ```
-    pub fn downgrade(self) -> RwLockReadGuard<'rwlock, T> {
-        // Reserve the read guard for ourselves
-        self.inner.acquire_reader();
-
-        let inner = self.inner;
+    pub fn try_with_upgraded<R, F: FnOnce(&mut T) -> R>(&mut self, f: F) -> Option<R> {
+        struct Restore<'a>(&'a NonAtomicState);
 
-        // Dropping self removes the UPGRADED bit
-        drop(self);
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                // Drops any UPGRADED bit set by a failed upgradeable read while we held the write lock.
+                self.0.store(UPGRADED, Ordering::Release);
+            }
+        }
 
-        RwLockReadGuard {
-            lock: &inner.lock,
-            data: unsafe { &*inner.data.get() },
+        let inner = self.inner;
+        inner
+            .lock
+            .compare_exchange(UPGRADED, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        let _restore = Restore(&inner.lock);
+
+        // Safety: We hold the write lock, and `&mut self` prevents any outstanding references through this guard
+        Some(f(unsafe { &mut *inner.data.get() }))
+    }
+
+    #[inline]
+    /// Downgrades the upgradeable lock guard to a readable, shared lock guard. Cannot fail and is guaranteed not to spin.
+    ///
+    /// ```
+    /// let mylock = nospin::RwLock::new(1);
+    ///
+    /// let upgradeable = mylock.upgradeable_read();
+    /// assert!(mylock.try_read().is_none());
+    /// assert_eq!(*upgradeable, 1);
+    ///
+    /// let readable = upgradeable.downgrade(); // This is guaranteed not to spin
+    /// assert!(mylock.try_read().is_some());
+    /// assert_eq!(*readable, 1);
+    /// ```
+    pub fn downgrade(self) -> RwLockReadGuard<'rwlock, T> {
+        let counted = self.inner.lock.load(Ordering::Relaxed) & !SATURATED <= MAX_READERS * READER
+            || too_many_readers();
+
+        let inner = self.inner;
+        forget(self);
+
+        // Trade the UPGRADED bit for a reader in a single step, so that the reader count never changes
+        if counted {
+            inner.lock.fetch_add(READER - UPGRADED, Ordering::Release);
+        } else {
+            inner.lock.fetch_sub(UPGRADED, Ordering::Release);
         }
+
+        RwLockReadGuard::new(&inner.lock, unsafe { &*inner.data.get() }, counted)
     }
 
     /// Leak the lock guard, yielding a reference to the underlying data.
@@ -670,7 +1814,13 @@ impl<'rwlock, T: ?Sized> RwLockUpgradableGuard<'rwlock, T> {
 
 impl<T: ?Sized + fmt::Debug> fmt::Debug for RwLockUpgradableGuard<'_, T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&**self, f)
+        if cfg!(feature = "verbose_debug") {
+            f.debug_tuple("RwLockUpgradableGuard")
+                .field(&&**self)
+                .finish()
+        } else {
+            fmt::Debug::fmt(&**self, f)
+        }
     }
 }
 
@@ -680,7 +1830,185 @@ impl<T: ?Sized + fmt::Display> fmt::Display for RwLockUpgradableGuard<'_, T> {
     }
 }
 
+impl<T: ?Sized> fmt::Pointer for RwLockUpgradableGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.data, f)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq<T> for RwLockUpgradableGuard<'_, T> {
+    fn eq(&self, other: &T) -> bool {
+        **self == *other
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for RwLockUpgradableGuard<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd<T> for RwLockUpgradableGuard<'_, T> {
+    fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(other)
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for RwLockUpgradableGuard<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
 impl<'rwlock, T: ?Sized> RwLockWriteGuard<'rwlock, T> {
+    /// Returns a reference to the locked data.
+    ///
+    /// This is equivalent to dereferencing the guard. It is an associated function that needs to be used as
+    /// `RwLockWriteGuard::get(...)`, so as not to conflict with a method of the same name on the locked data.
+    #[inline(always)]
+    pub fn get(this: &Self) -> &T {
+        unsafe { &*this.data }
+    }
+
+    /// Returns a mutable reference to the locked data.
+    ///
+    /// This is equivalent to mutably dereferencing the guard. It is an associated function that needs to be
+    /// used as `RwLockWriteGuard::get_mut(...)`, so as not to conflict with a method of the same name on the locked data.
+    ///
+    /// ```
+    /// use nospin::RwLockWriteGuard;
+    ///
+    /// let lock = nospin::RwLock::new(0);
+    ///
+    /// let mut guard = lock.write();
+    /// *RwLockWriteGuard::get_mut(&mut guard) += 1;
+    /// assert_eq!(*RwLockWriteGuard::get(&guard), 1);
+    /// ```
+    #[inline(always)]
+    pub fn get_mut(this: &mut Self) -> &mut T {
+        unsafe { &mut *this.data }
+    }
+
+    /// Returns a non-null pointer to the locked data, such as for handing it to C.
+    ///
+    /// The pointer is only valid while the guard is alive. It is an associated function that needs to be used as
+    /// `RwLockWriteGuard::as_non_null(...)`, so as not to conflict with a method of the same name on the locked
+    /// data.
+    #[inline(always)]
+    pub fn as_non_null(this: &Self) -> NonNull<T> {
+        unsafe { NonNull::new_unchecked(this.data) }
+    }
+
+    /// Runs `f` with shared access to the locked data.
+    ///
+    /// This is equivalent to passing `&*guard` to `f`, and so the lock remains write locked while `f` runs. Use
+    /// [`RwLockWriteGuard::downgraded_scope`] if `f` may need to read the lock itself. It is an associated function
+    /// that needs to be used as `RwLockWriteGuard::with_shared(...)`, so as not to conflict with a method of the same
+    /// name on the locked data.
+    ///
+    /// ```
+    /// use nospin::RwLockWriteGuard;
+    ///
+    /// let lock = nospin::RwLock::new(vec![1, 2]);
+    /// let guard = lock.write();
+    /// assert_eq!(RwLockWriteGuard::with_shared(&guard, |v| v.len()), 2);
+    /// ```
+    #[inline(always)]
+    pub fn with_shared<R, F: FnOnce(&T) -> R>(this: &Self, f: F) -> R {
+        f(unsafe { &*this.data })
+    }
+
+    /// Downgrades the lock to a read lock while running `f` with shared access to the data, then write locks it
+    /// again for a new guard.
+    ///
+    /// While `f` runs, the lock may be read again, for example by recursive code that only has access to the
+    /// [`RwLock`] itself.
+    ///
+    /// The write lock can only be taken back once every other guard acquired during `f` has been released. Since
+    /// there is no one else to wait for in single-threaded code, if one is still held when `f` returns (for example
+    /// because it was leaked or stored elsewhere), the result of `f` is returned as `Err` along with a read guard
+    /// in place of the write guard. Should `f` panic, the downgraded read is released while unwinding.
+    ///
+    /// ```
+    /// use nospin::{RwLock, RwLockWriteGuard};
+    ///
+    /// fn total(lock: &RwLock<Vec<u32>>) -> u32 {
+    ///     lock.read().iter().sum()
+    /// }
+    ///
+    /// let lock = RwLock::new(vec![1, 2]);
+    /// let mut guard = lock.write();
+    /// guard.push(3);
+    ///
+    /// let (sum, mut guard) = RwLockWriteGuard::downgraded_scope(guard, |_| total(&lock)).unwrap();
+    /// assert_eq!(sum, 6);
+    /// guard.push(4);
+    /// assert!(lock.try_read().is_none());
+    /// ```
+    pub fn downgraded_scope<R, F: FnOnce(&T) -> R>(
+        this: Self,
+        f: F,
+    ) -> Result<(R, Self), (R, RwLockReadGuard<'rwlock, T>)> {
+        let inner = this.inner;
+        let read = this.downgrade();
+
+        let result = f(&read);
+
+        // Trade our read for the write lock in a single step, which only succeeds if it is the last guard left
+        if inner
+            .lock
+            .compare_exchange(READER, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err((result, read));
+        }
+        // The write guard takes over the record of the read
+        forget(read);
+        Ok((
+            result,
+            RwLockWriteGuard {
+                inner,
+                data: inner.data.get(),
+            },
+        ))
+    }
+
+    /// Releases the write lock while running `f`, then acquires it again for a new guard.
+    ///
+    /// While `f` runs, the lock may be read or written, for example by re-entrant code that only has access to the
+    /// [`RwLock`] itself, so the data seen through the returned guard may have been mutated.
+    ///
+    /// The guard is consumed, so the data cannot be reached through it while the lock is released. Since there is
+    /// no one else to wait for in single-threaded code, if the lock is still held when `f` returns (for example
+    /// because a guard was leaked), the write lock is not taken back and the result of `f` is returned as `Err`.
+    /// Should `f` panic, the lock is left released.
+    ///
+    /// ```
+    /// use nospin::{RwLock, RwLockWriteGuard};
+    ///
+    /// let lock = RwLock::new(vec![1]);
+    /// let guard = lock.write();
+    ///
+    /// let (len, mut guard) = RwLockWriteGuard::unlocked(guard, || {
+    ///     lock.write().push(2);
+    ///     lock.read().len()
+    /// })
+    /// .unwrap();
+    /// guard.push(3);
+    /// assert_eq!((len, guard.len()), (2, 3));
+    /// ```
+    pub fn unlocked<R, F: FnOnce() -> R>(this: Self, f: F) -> Result<(R, Self), R> {
+        let inner = this.inner;
+        drop(this);
+
+        let result = f();
+
+        match inner.try_write() {
+            Some(guard) => Ok((result, guard)),
+            None => Err(result),
+        }
+    }
+
     /// Downgrades the writable lock guard to a readable, shared lock guard. Cannot fail and is guaranteed not to spin.
     ///
     /// ```
@@ -696,17 +2024,18 @@ impl<'rwlock, T: ?Sized> RwLockWriteGuard<'rwlock, T> {
     #[inline]
     pub fn downgrade(self) -> RwLockReadGuard<'rwlock, T> {
         // Reserve the read guard for ourselves
-        self.inner.acquire_reader();
+        let (_, counted) = self.inner.lock.acquire_reader();
 
         let inner = self.inner;
 
+        // The read guard keeps the lock recorded as held once self is dropped
+        #[cfg(feature = "lock_order")]
+        lock_order::reacquired(&inner.lock);
+
         // Dropping self removes the UPGRADED bit
         drop(self);
 
-        RwLockReadGuard {
-            lock: &inner.lock,
-            data: unsafe { &*inner.data.get() },
-        }
+        RwLockReadGuard::new(&inner.lock, unsafe { &*inner.data.get() }, counted)
     }
 
     /// Downgrades the writable lock guard to an upgradable, shared lock guard. Cannot fail and is guaranteed not to spin.
@@ -722,7 +2051,7 @@ impl<'rwlock, T: ?Sized> RwLockWriteGuard<'rwlock, T> {
     /// ```
     #[inline]
     pub fn downgrade_to_upgradeable(self) -> RwLockUpgradableGuard<'rwlock, T> {
-        debug_assert_eq!(
+        debug_check_eq!(
             self.inner.lock.load(Ordering::Acquire) & (WRITER | UPGRADED),
             WRITER
         );
@@ -759,11 +2088,40 @@ impl<'rwlock, T: ?Sized> RwLockWriteGuard<'rwlock, T> {
         // Safety: We know statically that only we are referencing data
         unsafe { &mut *this.data }
     }
+
+    /// Makes a new [`MappedRwLockWriteGuard`] for a component of the locked data.
+    ///
+    /// This is an associated function that needs to be used as `RwLockWriteGuard::map(...)`, so as not to
+    /// conflict with a method of the same name on the locked data.
+    ///
+    /// ```
+    /// let mylock = nospin::RwLock::new((1, 2));
+    ///
+    /// let mut second = nospin::RwLockWriteGuard::map(mylock.write(), |pair| &mut pair.1);
+    /// *second = 3;
+    /// drop(second);
+    ///
+    /// assert_eq!(*mylock.read(), (1, 3));
+    /// ```
+    #[inline]
+    pub fn map<U: ?Sized, F: FnOnce(&mut T) -> &mut U>(
+        this: Self,
+        f: F,
+    ) -> MappedRwLockWriteGuard<'rwlock, U> {
+        let data: *mut U = f(unsafe { &mut *this.data });
+        let lock = &this.inner.lock;
+        forget(this);
+        MappedRwLockWriteGuard { lock, data }
+    }
 }
 
 impl<T: ?Sized + fmt::Debug> fmt::Debug for RwLockWriteGuard<'_, T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(&**self, f)
+        if cfg!(feature = "verbose_debug") {
+            f.debug_tuple("RwLockWriteGuard").field(&&**self).finish()
+        } else {
+            fmt::Debug::fmt(&**self, f)
+        }
     }
 }
 
@@ -773,16 +2131,411 @@ impl<T: ?Sized + fmt::Display> fmt::Display for RwLockWriteGuard<'_, T> {
     }
 }
 
-impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
-    type Target = T;
-
-    fn deref(&self) -> &T {
-        // Safety: We know statically that only we are referencing data
-        unsafe { &*self.data }
+impl<T: ?Sized> fmt::Pointer for RwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.data, f)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq<T> for RwLockWriteGuard<'_, T> {
+    fn eq(&self, other: &T) -> bool {
+        **self == *other
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for RwLockWriteGuard<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd<T> for RwLockWriteGuard<'_, T> {
+    fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(other)
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for RwLockWriteGuard<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized> TryLock for RwLock<T> {
+    type Target = T;
+    type Guard<'a>
+        = RwLockWriteGuard<'a, T>
+    where
+        Self: 'a;
+
+    #[inline(always)]
+    fn try_lock(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        RwLock::try_write(self)
+    }
+}
+
+impl<T: ?Sized> Guard for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn get(&self) -> &T {
+        RwLockReadGuard::get(self)
+    }
+}
+
+impl<T: ?Sized> Guard for RwLockUpgradableGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn get(&self) -> &T {
+        RwLockUpgradableGuard::get(self)
+    }
+}
+
+impl<T: ?Sized> Guard for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn get(&self) -> &T {
+        RwLockWriteGuard::get(self)
+    }
+}
+
+impl<T: ?Sized> GuardMut for RwLockWriteGuard<'_, T> {
+    #[inline(always)]
+    fn get_mut(&mut self) -> &mut T {
+        RwLockWriteGuard::get_mut(self)
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: We know statically that only we are referencing data
+        unsafe { &*self.data }
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockUpgradableGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: We know statically that only we are referencing data
+        unsafe { &*self.data }
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: We know statically that only we are referencing data
+        unsafe { &*self.data }
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // Safety: We know statically that only we are referencing data
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        #[cfg(feature = "lock_order")]
+        lock_order::released(self.lock);
+
+        #[cfg(feature = "saturating_readers")]
+        if !self.counted {
+            return;
+        }
+
+        debug_check!(self.lock.load(Ordering::Relaxed) & !(WRITER | UPGRADED) > 0);
+        self.lock.fetch_sub(READER, Ordering::Release);
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockUpgradableGuard<'_, T> {
+    fn drop(&mut self) {
+        debug_check_eq!(
+            self.inner.lock.load(Ordering::Relaxed) & (WRITER | UPGRADED),
+            UPGRADED
+        );
+        #[cfg(feature = "lock_order")]
+        lock_order::released(&self.inner.lock);
+        self.inner.lock.fetch_sub(UPGRADED, Ordering::AcqRel);
+    }
+}
+
+#[cfg(feature = "embedded_io")]
+impl<D: ?Sized + embedded_io::ErrorType> embedded_io::ErrorType for RwLockWriteGuard<'_, D> {
+    type Error = D::Error;
+}
+
+#[cfg(feature = "embedded_io")]
+impl<D: ?Sized + embedded_io::Read> embedded_io::Read for RwLockWriteGuard<'_, D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        (**self).read(buf)
+    }
+}
+
+#[cfg(feature = "embedded_io")]
+impl<D: ?Sized + embedded_io::Write> embedded_io::Write for RwLockWriteGuard<'_, D> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        (**self).write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        (**self).flush()
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        debug_check_eq!(self.inner.lock.load(Ordering::Relaxed) & WRITER, WRITER);
+        #[cfg(feature = "lock_order")]
+        lock_order::released(&self.inner.lock);
+
+        // Writer is responsible for clearing both WRITER and UPGRADED bits.
+        // The UPGRADED bit may be set if an upgradeable lock attempts an upgrade while this lock is held.
+        self.inner
+            .lock
+            .fetch_and(!(WRITER | UPGRADED), Ordering::Release);
+    }
+}
+
+impl<'rwlock, T: ?Sized> MappedRwLockReadGuard<'rwlock, T> {
+    // Creates a guard for a reader acquired with `NonAtomicState::acquire_reader`.
+    #[inline(always)]
+    #[cfg_attr(not(feature = "saturating_readers"), allow(unused_variables))]
+    fn new(lock: &'rwlock NonAtomicState, data: *const T, counted: bool) -> Self {
+        MappedRwLockReadGuard {
+            lock,
+            data,
+            #[cfg(feature = "saturating_readers")]
+            counted,
+        }
+    }
+
+    /// Returns a reference to the locked data.
+    ///
+    /// This is equivalent to dereferencing the guard. It is an associated function that needs to be used as
+    /// `MappedRwLockReadGuard::get(...)`, so as not to conflict with a method of the same name on the locked data.
+    #[inline(always)]
+    pub fn get(this: &Self) -> &T {
+        unsafe { &*this.data }
+    }
+
+    /// Makes a new [`MappedRwLockReadGuard`] for a component of the already mapped data.
+    ///
+    /// This is an associated function that needs to be used as `MappedRwLockReadGuard::map(...)`, so as
+    /// not to conflict with a method of the same name on the locked data.
+    #[inline]
+    pub fn map<U: ?Sized, F: FnOnce(&T) -> &U>(
+        this: Self,
+        f: F,
+    ) -> MappedRwLockReadGuard<'rwlock, U> {
+        let data: *const U = f(unsafe { &*this.data });
+        let this = ManuallyDrop::new(this);
+        MappedRwLockReadGuard {
+            lock: this.lock,
+            data,
+            #[cfg(feature = "saturating_readers")]
+            counted: this.counted,
+        }
+    }
+}
+
+impl<'rwlock, T: ?Sized> MappedRwLockWriteGuard<'rwlock, T> {
+    /// Returns a reference to the locked data.
+    ///
+    /// This is equivalent to dereferencing the guard. It is an associated function that needs to be used as
+    /// `MappedRwLockWriteGuard::get(...)`, so as not to conflict with a method of the same name on the locked data.
+    #[inline(always)]
+    pub fn get(this: &Self) -> &T {
+        unsafe { &*this.data }
+    }
+
+    /// Returns a mutable reference to the locked data.
+    ///
+    /// This is equivalent to mutably dereferencing the guard. It is an associated function that needs to be
+    /// used as `MappedRwLockWriteGuard::get_mut(...)`, so as not to conflict with a method of the same name on the locked data.
+    #[inline(always)]
+    pub fn get_mut(this: &mut Self) -> &mut T {
+        unsafe { &mut *this.data }
+    }
+
+    /// Makes a new [`MappedRwLockWriteGuard`] for a component of the already mapped data.
+    ///
+    /// This is an associated function that needs to be used as `MappedRwLockWriteGuard::map(...)`, so as
+    /// not to conflict with a method of the same name on the locked data.
+    #[inline]
+    pub fn map<U: ?Sized, F: FnOnce(&mut T) -> &mut U>(
+        this: Self,
+        f: F,
+    ) -> MappedRwLockWriteGuard<'rwlock, U> {
+        let data: *mut U = f(unsafe { &mut *this.data });
+        let lock = this.lock;
+        forget(this);
+        MappedRwLockWriteGuard { lock, data }
+    }
+
+    /// Downgrades the mapped writable lock guard to a mapped readable guard for the same component of the data.
+    /// Cannot fail and is guaranteed not to spin.
+    ///
+    /// ```
+    /// let mylock = nospin::RwLock::new((1, 2));
+    ///
+    /// let mut second = nospin::RwLockWriteGuard::map(mylock.write(), |pair| &mut pair.1);
+    /// *second = 3;
+    ///
+    /// let second = second.downgrade();
+    /// assert!(mylock.try_read().is_some());
+    /// assert_eq!(*second, 3);
+    /// ```
+    #[inline]
+    pub fn downgrade(self) -> MappedRwLockReadGuard<'rwlock, T> {
+        // Reserve the read guard for ourselves
+        let (_, counted) = self.lock.acquire_reader();
+
+        let lock = self.lock;
+        let data = self.data;
+
+        // The read guard keeps the lock recorded as held once self is dropped
+        #[cfg(feature = "lock_order")]
+        lock_order::reacquired(lock);
+
+        // Dropping self removes the WRITER and UPGRADED bits
+        drop(self);
+
+        MappedRwLockReadGuard::new(lock, data, counted)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for MappedRwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if cfg!(feature = "verbose_debug") {
+            f.debug_tuple("MappedRwLockReadGuard")
+                .field(&&**self)
+                .finish()
+        } else {
+            fmt::Debug::fmt(&**self, f)
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for MappedRwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> fmt::Pointer for MappedRwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.data, f)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq<T> for MappedRwLockReadGuard<'_, T> {
+    fn eq(&self, other: &T) -> bool {
+        **self == *other
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for MappedRwLockReadGuard<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd<T> for MappedRwLockReadGuard<'_, T> {
+    fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(other)
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for MappedRwLockReadGuard<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for MappedRwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if cfg!(feature = "verbose_debug") {
+            f.debug_tuple("MappedRwLockWriteGuard")
+                .field(&&**self)
+                .finish()
+        } else {
+            fmt::Debug::fmt(&**self, f)
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for MappedRwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> fmt::Pointer for MappedRwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Pointer::fmt(&self.data, f)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq<T> for MappedRwLockWriteGuard<'_, T> {
+    fn eq(&self, other: &T) -> bool {
+        **self == *other
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for MappedRwLockWriteGuard<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd<T> for MappedRwLockWriteGuard<'_, T> {
+    fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(other)
+    }
+}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for MappedRwLockWriteGuard<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: ?Sized> Guard for MappedRwLockReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn get(&self) -> &T {
+        MappedRwLockReadGuard::get(self)
+    }
+}
+
+impl<T: ?Sized> Guard for MappedRwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn get(&self) -> &T {
+        MappedRwLockWriteGuard::get(self)
+    }
+}
+
+impl<T: ?Sized> GuardMut for MappedRwLockWriteGuard<'_, T> {
+    #[inline(always)]
+    fn get_mut(&mut self) -> &mut T {
+        MappedRwLockWriteGuard::get_mut(self)
     }
 }
 
-impl<T: ?Sized> Deref for RwLockUpgradableGuard<'_, T> {
+impl<T: ?Sized> Deref for MappedRwLockReadGuard<'_, T> {
     type Target = T;
 
     fn deref(&self) -> &T {
@@ -791,7 +2544,7 @@ impl<T: ?Sized> Deref for RwLockUpgradableGuard<'_, T> {
     }
 }
 
-impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+impl<T: ?Sized> Deref for MappedRwLockWriteGuard<'_, T> {
     type Target = T;
 
     fn deref(&self) -> &T {
@@ -800,40 +2553,50 @@ impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
     }
 }
 
-impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+impl<T: ?Sized> DerefMut for MappedRwLockWriteGuard<'_, T> {
     fn deref_mut(&mut self) -> &mut T {
         // Safety: We know statically that only we are referencing data
         unsafe { &mut *self.data }
     }
 }
 
-impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+impl<T: ?Sized> Drop for MappedRwLockReadGuard<'_, T> {
     fn drop(&mut self) {
-        debug_assert!(self.lock.load(Ordering::Relaxed) & !(WRITER | UPGRADED) > 0);
-        self.lock.fetch_sub(READER, Ordering::Release);
-    }
-}
+        #[cfg(feature = "lock_order")]
+        lock_order::released(self.lock);
 
-impl<T: ?Sized> Drop for RwLockUpgradableGuard<'_, T> {
-    fn drop(&mut self) {
-        debug_assert_eq!(
-            self.inner.lock.load(Ordering::Relaxed) & (WRITER | UPGRADED),
-            UPGRADED
-        );
-        self.inner.lock.fetch_sub(UPGRADED, Ordering::AcqRel);
+        #[cfg(feature = "saturating_readers")]
+        if !self.counted {
+            return;
+        }
+
+        debug_check!(self.lock.load(Ordering::Relaxed) & !(WRITER | UPGRADED) > 0);
+        self.lock.fetch_sub(READER, Ordering::Release);
     }
 }
 
-impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+impl<T: ?Sized> Drop for MappedRwLockWriteGuard<'_, T> {
     fn drop(&mut self) {
-        debug_assert_eq!(self.inner.lock.load(Ordering::Relaxed) & WRITER, WRITER);
+        debug_check_eq!(self.lock.load(Ordering::Relaxed) & WRITER, WRITER);
+        #[cfg(feature = "lock_order")]
+        lock_order::released(self.lock);
 
         // Writer is responsible for clearing both WRITER and UPGRADED bits.
         // The UPGRADED bit may be set if an upgradeable lock attempts an upgrade while this lock is held.
-        self.inner
-            .lock
-            .fetch_and(!(WRITER | UPGRADED), Ordering::Release);
+        self.lock.fetch_and(!(WRITER | UPGRADED), Ordering::Release);
+    }
+}
+
+// Keeps a read lock acquired on behalf of `lock_api` held, returning whether it was. `lock_api` releases every read
+// lock the same way, so a reader left uncounted by `saturating_readers` is given up rather than kept.
+#[cfg(feature = "lock_api")]
+fn hold_for_lock_api(guard: RwLockReadGuard<'_, ()>) -> bool {
+    #[cfg(feature = "saturating_readers")]
+    if !guard.counted {
+        return false;
     }
+    forget(guard);
+    true
 }
 
 #[cfg(feature = "lock_api")]
@@ -865,22 +2628,19 @@ unsafe impl lock_api_crate::RawRwLock for RwLock<()> {
 
     #[inline(always)]
     fn lock_shared(&self) {
-        // Prevent guard destructor running
-        core::mem::forget(self.read());
+        if !hold_for_lock_api(self.read()) {
+            panic!("Too many lock readers, cannot safely proceed");
+        }
     }
 
     #[inline(always)]
     fn try_lock_shared(&self) -> bool {
-        // Prevent guard destructor running
-        self.try_read().map(core::mem::forget).is_some()
+        self.try_read().is_some_and(hold_for_lock_api)
     }
 
     #[inline(always)]
     unsafe fn unlock_shared(&self) {
-        drop(RwLockReadGuard {
-            lock: &self.lock,
-            data: &(),
-        });
+        drop(RwLockReadGuard::new(&self.lock, &(), true));
     }
 
     #[inline(always)]
@@ -889,6 +2649,24 @@ unsafe impl lock_api_crate::RawRwLock for RwLock<()> {
     }
 }
 
+#[cfg(feature = "lock_api")]
+unsafe impl lock_api_crate::RawRwLockRecursive for RwLock<()> {
+    #[inline(always)]
+    fn lock_shared_recursive(&self) {
+        let guard = self
+            .try_read_recursive()
+            .expect("Failed to get read lock, who are you waiting for?");
+        if !hold_for_lock_api(guard) {
+            panic!("Too many lock readers, cannot safely proceed");
+        }
+    }
+
+    #[inline(always)]
+    fn try_lock_shared_recursive(&self) -> bool {
+        self.try_read_recursive().is_some_and(hold_for_lock_api)
+    }
+}
+
 #[cfg(feature = "lock_api")]
 unsafe impl lock_api_crate::RawRwLockUpgrade for RwLock<()> {
     #[inline(always)]
@@ -944,6 +2722,10 @@ unsafe impl lock_api_crate::RawRwLockDowngrade for RwLock<()> {
 #[cfg(feature = "lock_api")]
 unsafe impl lock_api_crate::RawRwLockUpgradeDowngrade for RwLock<()> {
     unsafe fn downgrade_upgradable(&self) {
+        // Checked before giving up the upgradeable lock, as the resulting reader must be counted to be released
+        if self.state_snapshot().readers >= Self::MAX_READERS {
+            panic!("Too many lock readers, cannot safely proceed");
+        }
         let tmp_guard = RwLockUpgradableGuard {
             inner: self,
             data: &(),
@@ -964,9 +2746,11 @@ unsafe impl lock_api_crate::RawRwLockUpgradeDowngrade for RwLock<()> {
 mod tests {
     use std::prelude::v1::*;
 
+    use super::WriteBlocked;
     use std::mem::forget;
     use std::sync::Arc;
     use std::sync::atomic::{AtomicUsize, Ordering};
+    #[cfg(not(feature = "strict_send_sync"))]
     use std::thread;
 
     type RwLock<T> = super::RwLock<T>;
@@ -983,6 +2767,7 @@ mod tests {
         drop(l.write());
     }
 
+    #[cfg(not(feature = "strict_send_sync"))]
     #[test]
     fn test_rw_access_in_unwind() {
         let arc = Arc::new(RwLock::new(1));
@@ -1065,6 +2850,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_force_read_decrement() {
         let m = RwLock::new(());
         forget(m.read());
@@ -1083,6 +2869,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_force_write_unlock() {
         let m = RwLock::new(());
         forget(m.write());
@@ -1093,6 +2880,473 @@ mod tests {
         assert!(m.try_read().is_some());
     }
 
+    #[test]
+    fn test_force_unlock_write_upgraded() {
+        let m = RwLock::new(());
+
+        // A failed upgradeable read leaves the UPGRADED bit behind for the writer to clear
+        forget(m.write());
+        assert!(m.try_upgradeable_read().is_none());
+        unsafe { m.force_unlock_write() };
+        assert!(m.flags().is_empty());
+        assert!(m.try_upgradeable_read().is_some());
+
+        // An upgraded guard hands over its UPGRADED bit, so nothing is left held
+        forget(m.upgradeable_read().upgrade());
+        assert!(m.try_upgradeable_read().is_none());
+        unsafe { m.force_unlock_write() };
+        assert!(m.flags().is_empty());
+    }
+
+    #[cfg(any(debug_assertions, feature = "debug_checks"))]
+    #[test]
+    #[should_panic(expected = "not write locked")]
+    fn test_force_unlock_write_not_locked() {
+        let m = RwLock::new(());
+        let _upgradeable = m.upgradeable_read();
+        unsafe { m.force_unlock_write() };
+    }
+
+    #[test]
+    fn test_guard_traits() {
+        use crate::traits::{Guard, GuardMut};
+
+        fn first<G: Guard<Target = u32>>(guard: G) -> u32 {
+            *guard.get()
+        }
+
+        fn bump<G: GuardMut<Target = u32>>(mut guard: G) {
+            *guard.get_mut() += 1;
+        }
+
+        let lock = RwLock::new((1u32, 2u32));
+        assert_eq!(first(super::RwLockReadGuard::map(lock.read(), |v| &v.0)), 1);
+        bump(super::RwLockWriteGuard::map(lock.write(), |v| &mut v.1));
+        assert_eq!(first(super::RwLockReadGuard::map(lock.read(), |v| &v.1)), 3);
+    }
+
+    #[test]
+    fn test_state_snapshot() {
+        let lock = RwLock::new(0);
+        let state = |readers, has_writer, has_upgradable| super::RwLockState {
+            readers,
+            has_writer,
+            has_upgradable,
+        };
+
+        assert_eq!(lock.state_snapshot(), state(0, false, false));
+        let w = lock.write();
+        assert_eq!(lock.state_snapshot(), state(0, true, false));
+        assert_eq!(format!("{:?}", lock), "RwLock { <locked> }");
+        assert_eq!(
+            format!("{:#?}", lock),
+            "RwLock {\n    readers: 0,\n    writer: true,\n    upgradable: false,\n    data: <locked>,\n}"
+        );
+        drop(w);
+
+        let r = lock.read();
+        let u = lock.upgradeable_read();
+        assert_eq!(lock.state_snapshot(), state(1, false, true));
+        assert_eq!(
+            format!("{:#?}", lock),
+            "RwLock {\n    readers: 1,\n    writer: false,\n    upgradable: true,\n    data: <locked>,\n}"
+        );
+        assert_eq!(lock.reader_count(), 2);
+        drop(u);
+        assert_eq!(
+            format!("{:#?}", lock),
+            "RwLock {\n    readers: 1,\n    writer: false,\n    upgradable: false,\n    data: 0,\n}"
+        );
+        drop(r);
+        assert_eq!(lock.state_snapshot(), state(0, false, false));
+    }
+
+    #[test]
+    fn test_ord() {
+        let a = RwLock::new(1);
+        let b = RwLock::new(2);
+        assert!(a < b);
+        assert_eq!(a, a);
+
+        let _r = a.read();
+        assert_eq!(a.cmp(&b), std::cmp::Ordering::Less);
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to get read lock for comparison")]
+    fn test_ord_write_locked() {
+        let a = RwLock::new(1);
+        let _w = a.write();
+        let _ = a == RwLock::new(1);
+    }
+
+    #[test]
+    fn test_flags() {
+        use super::RwLockFlags;
+
+        let lock = RwLock::new(());
+        assert!(lock.flags().is_empty());
+
+        let w = lock.write();
+        assert_eq!(lock.flags(), RwLockFlags::WRITER);
+        drop(w);
+
+        let r1 = lock.read();
+        let r2 = lock.read();
+        assert_eq!(lock.flags(), RwLockFlags::READER);
+        assert_eq!(lock.flags().bits(), 1 << 2);
+        drop((r1, r2));
+
+        let u = lock.upgradeable_read();
+        assert!(lock.flags().contains(RwLockFlags::UPGRADED));
+        assert!(
+            !lock
+                .flags()
+                .contains(RwLockFlags::UPGRADED | RwLockFlags::READER)
+        );
+        drop(u);
+        assert_eq!(lock.flags(), RwLockFlags::empty());
+    }
+
+    #[test]
+    fn test_new_locked() {
+        let lock = RwLock::new_read_locked((), 1);
+        assert_eq!(lock.try_write_err().err(), Some(WriteBlocked::Readers(1)));
+        assert!(lock.try_read().is_some());
+
+        let lock = RwLock::new_write_locked(());
+        assert_eq!(lock.try_write_err().err(), Some(WriteBlocked::Writer));
+        assert!(lock.try_upgradeable_read().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "too many readers")]
+    fn test_new_read_locked_overflow() {
+        let _ = RwLock::new_read_locked((), usize::MAX);
+    }
+
+    #[test]
+    fn test_try_upgrade_err() {
+        use super::UpgradeBlocked;
+
+        let lock = RwLock::new(0);
+        let r1 = lock.read();
+        let r2 = lock.read();
+        let (u, reason) = lock.upgradeable_read().try_upgrade_err().err().unwrap();
+        assert_eq!(reason, UpgradeBlocked::Readers(2));
+        drop((r1, r2));
+
+        // Simulate a misused force unlock leaving a writer alongside the upgradeable guard
+        lock.lock.fetch_or(super::WRITER, super::Ordering::Relaxed);
+        let (u, reason) = u.try_upgrade_err().err().unwrap();
+        assert_eq!(reason, UpgradeBlocked::InvalidState);
+        lock.lock
+            .fetch_and(!super::WRITER, super::Ordering::Relaxed);
+
+        assert!(u.try_upgrade_err().is_ok());
+    }
+
+    #[test]
+    fn test_read_then_maybe_write() {
+        use core::ops::ControlFlow;
+        use std::panic;
+
+        let lock = RwLock::new(0);
+        let r = lock.read_then_maybe_write(|_| ControlFlow::Break(1), |_| unreachable!());
+        assert_eq!(r, 1);
+        let r = lock.read_then_maybe_write(
+            |v| {
+                assert!(lock.try_write().is_none());
+                if *v == 0 {
+                    ControlFlow::Continue(())
+                } else {
+                    ControlFlow::Break(*v)
+                }
+            },
+            |v| {
+                *v = 5;
+                *v
+            },
+        );
+        assert_eq!(r, 5);
+        assert_eq!((lock.reader_count(), lock.writer_count()), (0, 0));
+
+        // A read still held when upgrading
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            lock.read_then_maybe_write(
+                |_| {
+                    forget(lock.try_read_recursive());
+                    ControlFlow::Continue(())
+                },
+                |_| (),
+            )
+        }));
+        assert!(t.is_err());
+        assert_eq!((lock.reader_count(), lock.writer_count()), (1, 0));
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let lock = RwLock::new(vec![1, 2, 3]);
+        lock.write().push(4);
+        assert_eq!(lock.into_iter().collect::<Vec<_>>(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_assert_unlocked() {
+        use std::panic;
+
+        fn message(lock: &RwLock<()>) -> String {
+            let t = panic::catch_unwind(panic::AssertUnwindSafe(|| lock.assert_unlocked()));
+            let payload = t.unwrap_err();
+            match payload.downcast_ref::<&str>() {
+                Some(msg) => msg.to_string(),
+                None => *payload.downcast::<String>().unwrap(),
+            }
+        }
+
+        let lock = RwLock::new(());
+        lock.assert_unlocked();
+
+        let w = lock.write();
+        assert_eq!(message(&lock), "RwLock is unexpectedly write locked");
+        drop(w);
+
+        let r = lock.read();
+        let u = lock.upgradeable_read();
+        assert_eq!(
+            message(&lock),
+            "RwLock is unexpectedly read locked by 1 reader(s) and an upgradeable reader"
+        );
+        drop((r, u));
+        lock.assert_unlocked();
+    }
+
+    #[test]
+    fn test_clone_guard() {
+        let lock = RwLock::new(5);
+        let a = lock.read();
+        let b = super::RwLockReadGuard::clone_guard(&a);
+        let c = super::RwLockReadGuard::clone_guard(&b);
+        assert_eq!(*a + *b + *c, 15);
+        assert_eq!(lock.reader_count(), 3);
+        drop(a);
+        drop(c);
+        assert!(lock.try_write().is_none());
+        drop(b);
+        assert!(lock.try_write().is_some());
+    }
+
+    #[test]
+    fn test_as_non_null() {
+        let lock = RwLock::new([1u8, 2, 3]);
+        let guard = lock.write();
+        let ptr = super::RwLockWriteGuard::as_non_null(&guard);
+        unsafe { ptr.as_ptr().cast::<u8>().write(4) };
+        drop(guard);
+
+        let guard = lock.read();
+        let ptr = super::RwLockReadGuard::as_non_null(&guard);
+        assert_eq!(ptr.as_ptr().cast_const(), &raw const *guard);
+        assert_eq!(unsafe { *ptr.as_ref() }, [4, 2, 3]);
+    }
+
+    #[test]
+    fn test_try_read_weak() {
+        let lock = RwLock::new(1);
+        let reader = lock.try_read_weak().unwrap();
+        assert_eq!(*reader, 1);
+        assert!(lock.try_write_weak().is_none());
+        drop(reader);
+
+        let writer = lock.try_write_weak().unwrap();
+        assert!(lock.try_read_weak().is_none());
+        drop(writer);
+    }
+
+    #[test]
+    fn test_try_read_recursive() {
+        let lock = RwLock::new(());
+
+        let upgradeable = lock.upgradeable_read();
+        let reader = lock.try_read_recursive().unwrap();
+        assert!(lock.try_read().is_none());
+        assert_eq!(lock.reader_count(), 2);
+        drop(reader);
+        drop(upgradeable);
+
+        let writer = lock.write();
+        assert!(lock.try_read_recursive().is_none());
+        drop(writer);
+        assert_eq!(lock.reader_count(), 0);
+    }
+
+    #[cfg(feature = "lock_api")]
+    #[test]
+    fn test_lock_api_recursive() {
+        use lock_api_crate::RawRwLockRecursive;
+
+        let lock = crate::lock_api::RwLock::new(1);
+        let upgradeable = lock.upgradable_read();
+        let reader = lock.try_read_recursive().unwrap();
+        assert_eq!(*reader + *upgradeable, 2);
+        drop(reader);
+        drop(upgradeable);
+
+        let raw = unsafe { lock.raw() };
+        let writer = lock.write();
+        assert!(!raw.try_lock_shared_recursive());
+        drop(writer);
+        raw.lock_shared_recursive();
+        assert_eq!(raw.reader_count(), 1);
+        unsafe { lock_api_crate::RawRwLock::unlock_shared(raw) };
+    }
+
+    #[cfg(all(feature = "lock_api", feature = "saturating_readers"))]
+    #[test]
+    fn test_lock_api_saturated() {
+        use lock_api_crate::{RawRwLock, RawRwLockRecursive, RawRwLockUpgradeDowngrade};
+        use std::panic;
+
+        let raw = RwLock::new_read_locked((), RwLock::<()>::MAX_READERS);
+        assert!(!raw.try_lock_shared());
+        assert!(!raw.try_lock_shared_recursive());
+        assert!(panic::catch_unwind(panic::AssertUnwindSafe(|| raw.lock_shared())).is_err());
+        assert_eq!(raw.reader_count(), RwLock::<()>::MAX_READERS);
+
+        forget(raw.upgradeable_read());
+        let t = panic::catch_unwind(panic::AssertUnwindSafe(|| unsafe {
+            raw.downgrade_upgradable()
+        }));
+        assert!(t.is_err());
+        assert!(raw.state_snapshot().has_upgradable);
+
+        unsafe { raw.unlock_shared() };
+        unsafe { raw.downgrade_upgradable() };
+        assert_eq!(raw.shared_reader_count(), RwLock::<()>::MAX_READERS);
+    }
+
+    #[test]
+    fn test_downgraded_scope() {
+        use std::panic;
+
+        let lock = RwLock::new(0);
+        let (doubled, guard) = super::RwLockWriteGuard::downgraded_scope(lock.write(), |v| {
+            assert_eq!(lock.reader_count(), 1);
+            *lock.read() + *v
+        })
+        .unwrap();
+        assert_eq!(doubled, 0);
+        assert_eq!(lock.writer_count(), 1);
+
+        // Unwinding out of the scope releases the lock
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            super::RwLockWriteGuard::downgraded_scope(guard, |_| panic!())
+        }));
+        assert!(result.is_err());
+        assert_eq!((lock.reader_count(), lock.writer_count()), (0, 0));
+
+        // A read kept from within the scope leaves the guard read locked rather than write locked
+        let mut kept = None;
+        let (_, read) =
+            super::RwLockWriteGuard::downgraded_scope(lock.write(), |_| kept = Some(lock.read()))
+                .unwrap_err();
+        assert_eq!((lock.reader_count(), lock.writer_count()), (2, 0));
+        drop(read);
+        drop(kept);
+        assert_eq!(lock.reader_count(), 0);
+    }
+
+    #[test]
+    fn test_unlocked() {
+        use std::panic;
+
+        let lock = RwLock::new(0);
+        let ((), reader) = super::RwLockReadGuard::unlocked(lock.read(), || {
+            assert_eq!(lock.reader_count(), 0);
+            *lock.write() += 1;
+        })
+        .unwrap();
+        assert_eq!((*reader, lock.reader_count()), (1, 1));
+
+        // Unwinding out of the closure leaves the lock released
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            super::RwLockReadGuard::unlocked(reader, || panic!())
+        }));
+        assert!(result.is_err());
+        assert_eq!(lock.reader_count(), 0);
+
+        // A write kept from within the closure prevents taking the read back
+        let mut kept = None;
+        let result = super::RwLockReadGuard::unlocked(lock.read(), || kept = Some(lock.write()));
+        assert!(result.is_err());
+        assert_eq!((lock.reader_count(), lock.writer_count()), (0, 1));
+        drop(kept);
+
+        let ((), writer) =
+            super::RwLockWriteGuard::unlocked(lock.write(), || *lock.write() += 1).unwrap();
+        assert_eq!((*writer, lock.writer_count()), (2, 1));
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            super::RwLockWriteGuard::unlocked(writer, || panic!())
+        }));
+        assert!(result.is_err());
+        assert_eq!(lock.writer_count(), 0);
+
+        let mut kept = None;
+        let result = super::RwLockWriteGuard::unlocked(lock.write(), || kept = Some(lock.read()));
+        assert!(result.is_err());
+        assert_eq!((lock.reader_count(), lock.writer_count()), (1, 0));
+        drop(kept);
+        assert_eq!(lock.reader_count(), 0);
+    }
+
+    #[test]
+    fn test_try_write_err() {
+        let lock = RwLock::new(());
+
+        let w = lock.write();
+        assert_eq!(lock.try_write_err().err(), Some(WriteBlocked::Writer));
+        // A failed upgradeable attempt leaves its bit behind, but the writer is still reported
+        assert!(lock.try_upgradeable_read().is_none());
+        assert_eq!(lock.try_write_err().err(), Some(WriteBlocked::Writer));
+        drop(w);
+
+        let r = lock.read();
+        let u = lock.upgradeable_read();
+        assert_eq!(lock.try_write_err().err(), Some(WriteBlocked::Upgradable));
+        drop(u);
+        assert_eq!(lock.try_write_err().err(), Some(WriteBlocked::Readers(1)));
+        drop(r);
+
+        assert!(lock.try_write_err().is_ok());
+    }
+
+    #[test]
+    fn test_force_unlock() {
+        let m = RwLock::new(());
+        forget(m.read());
+        forget(m.read());
+        unsafe {
+            m.force_unlock_read();
+        }
+        assert_eq!(m.reader_count(), 1);
+        unsafe {
+            m.force_unlock_read();
+        }
+        forget(m.write());
+        unsafe {
+            m.force_unlock_write();
+        }
+        forget(m.upgradeable_read());
+        assert!(m.try_upgradeable_read().is_none());
+        unsafe {
+            m.force_unlock_upgradeable();
+        }
+        assert_eq!(m.reader_count(), 0);
+        assert!(m.try_write().is_some());
+    }
+
     #[test]
     fn test_upgrade_downgrade() {
         let m = RwLock::new(());
@@ -1118,4 +3372,249 @@ mod tests {
 
         assert!(m.try_upgradeable_read().unwrap().try_upgrade().is_ok());
     }
+
+    #[test]
+    fn test_map() {
+        let m = RwLock::new((1, vec![2]));
+        {
+            let mut w = super::RwLockWriteGuard::map(m.write(), |x| &mut x.1);
+            w.push(3);
+            let mut w = super::MappedRwLockWriteGuard::map(w, |v| &mut v[0]);
+            *w = 4;
+            assert!(m.try_read().is_none());
+
+            let r = w.downgrade();
+            assert_eq!(*r, 4);
+            assert_eq!(m.reader_count(), 1);
+            assert_eq!(m.writer_count(), 0);
+            assert!(m.try_write().is_none());
+        }
+        {
+            let r = super::RwLockReadGuard::map(m.read(), |x| &x.1);
+            let r = super::MappedRwLockReadGuard::map(r, |v| &v[1]);
+            assert_eq!(*r, 3);
+            assert!(m.try_write().is_none());
+        }
+        assert_eq!(*m.write(), (1, vec![4, 3]));
+    }
+
+    #[test]
+    fn test_guard_get() {
+        use super::{
+            MappedRwLockReadGuard, MappedRwLockWriteGuard, RwLockReadGuard, RwLockUpgradableGuard,
+            RwLockWriteGuard,
+        };
+
+        let m = RwLock::new((1, 2));
+        {
+            let mut w = m.write();
+            RwLockWriteGuard::get_mut(&mut w).0 = 3;
+            assert_eq!(RwLockWriteGuard::get(&w), &(3, 2));
+            let mut w = RwLockWriteGuard::map(w, |x| &mut x.1);
+            *MappedRwLockWriteGuard::get_mut(&mut w) = 4;
+            assert_eq!(MappedRwLockWriteGuard::get(&w), &4);
+        }
+        assert_eq!(RwLockReadGuard::get(&m.read()), &(3, 4));
+        assert_eq!(RwLockUpgradableGuard::get(&m.upgradeable_read()), &(3, 4));
+        let r = RwLockReadGuard::map(m.read(), |x| &x.0);
+        assert_eq!(MappedRwLockReadGuard::get(&r), &3);
+    }
+
+    #[test]
+    fn test_upgradeable_downgrade_reader_count() {
+        let m = RwLock::new(());
+        let _r = m.read();
+        let upg = m.upgradeable_read();
+        assert_eq!(m.reader_count(), 2);
+
+        let r = upg.downgrade();
+        assert_eq!(m.reader_count(), 2);
+        assert!(m.try_upgradeable_read().is_some());
+
+        drop(r);
+        assert_eq!(m.reader_count(), 1);
+    }
+
+    #[cfg(not(feature = "strict_send_sync"))]
+    #[test]
+    fn init_constant() {
+        static L: [RwLock<()>; 4] = [RwLock::INIT; 4];
+        let _w = L[0].write();
+        assert!(L[0].try_read().is_none());
+        assert!(L[1].try_write().is_some());
+    }
+
+    #[test]
+    fn test_guard_cmp() {
+        let a = RwLock::new(5);
+        let b = RwLock::new(7);
+        let (r1, r2) = (a.read(), a.read());
+        assert_eq!(r1, 5);
+        assert_eq!(r1, r2);
+        let w = b.write();
+        assert_eq!(w, 7);
+        assert!(*r1 < *w && r1 < 6 && w > 6);
+        drop((r1, r2, w));
+        let u = a.upgradeable_read();
+        assert_eq!(u, 5);
+        assert!(u <= 5);
+        drop(u);
+
+        let (r1, r2) = (
+            super::RwLockReadGuard::map(a.read(), |x| x),
+            super::RwLockReadGuard::map(a.read(), |x| x),
+        );
+        assert_eq!(r1, 5);
+        assert_eq!(r1, r2);
+        assert!(r1 < 6 && r1 <= r2);
+        let w = super::RwLockWriteGuard::map(b.write(), |x| x);
+        assert_eq!(w, 7);
+        assert!(w > 6 && w >= super::RwLockWriteGuard::map(RwLock::new(7).write(), |x| x));
+    }
+
+    #[cfg(feature = "saturating_readers")]
+    #[test]
+    #[allow(clippy::unnecessary_cast)]
+    fn test_saturating_readers() {
+        let m = RwLock::new(());
+        let cap = RwLock::<()>::MAX_READERS;
+        m.lock.store(
+            (cap * super::READER as usize) as _,
+            super::Ordering::Relaxed,
+        );
+
+        let r = m.read();
+        assert_eq!(m.reader_count(), cap);
+        drop(r);
+        assert_eq!(m.reader_count(), cap);
+        unsafe { m.force_unlock_read() };
+        assert_eq!(m.reader_count(), cap - 1);
+    }
+
+    #[cfg(feature = "saturating_readers")]
+    #[test]
+    #[allow(clippy::unnecessary_cast)]
+    fn test_saturated_refuses_writers() {
+        let cap = RwLock::<()>::MAX_READERS;
+        let m = RwLock::new_read_locked((), cap);
+        let uncounted = m.read();
+
+        // Release every counted reader while the uncounted one is still alive
+        m.lock.fetch_sub(
+            (cap * super::READER as usize) as _,
+            super::Ordering::Relaxed,
+        );
+        assert_eq!(m.reader_count(), 0);
+        assert!(m.try_write().is_none());
+        assert!(m.upgradeable_read().try_upgrade().is_err());
+        drop(uncounted);
+
+        assert!(m.try_write().is_none());
+        assert!(m.try_read().is_some());
+    }
+
+    #[cfg(feature = "saturating_readers")]
+    #[test]
+    #[allow(clippy::unnecessary_cast)]
+    fn test_failed_read_does_not_saturate() {
+        let cap = RwLock::<()>::MAX_READERS;
+        let m = RwLock::new_read_locked((), cap);
+        let upgradeable = m.upgradeable_read();
+        assert!(m.try_read().is_none());
+
+        drop(upgradeable);
+        m.lock.fetch_sub(
+            (cap * super::READER as usize) as _,
+            super::Ordering::Relaxed,
+        );
+        assert!(m.try_write().is_some());
+    }
+
+    #[cfg(all(feature = "small_state", not(feature = "lock_order")))]
+    #[test]
+    fn test_small_state() {
+        assert_eq!(core::mem::size_of::<RwLock<()>>(), 2);
+    }
+
+    #[cfg(feature = "small_state")]
+    #[test]
+    fn test_small_state_locking() {
+        assert_eq!(RwLock::<()>::MAX_READERS, 8191);
+
+        let m = RwLock::new(0);
+        *m.write() = 5;
+        assert_eq!(m.writer_count(), 0);
+        assert_eq!(*m.read(), 5);
+
+        // Every reader up to the cap is counted in the 16-bit state word, next to the writer and upgraded bits
+        let readers = (0..RwLock::<()>::MAX_READERS)
+            .map(|_| m.try_read_recursive().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(m.reader_count(), RwLock::<()>::MAX_READERS);
+        assert!(m.try_write().is_none());
+        assert!(m.try_upgradeable_read().is_some());
+        drop(readers);
+
+        assert_eq!(m.reader_count(), 0);
+        *m.upgradeable_read().upgrade() += 1;
+        assert_eq!(m.into_inner(), 6);
+    }
+
+    #[test]
+    fn test_guard_debug() {
+        let m = RwLock::new(5);
+        let expected = |name: &str| {
+            if cfg!(feature = "verbose_debug") {
+                format!("{}(5)", name)
+            } else {
+                "5".to_string()
+            }
+        };
+        assert_eq!(format!("{:?}", m.read()), expected("RwLockReadGuard"));
+        assert_eq!(
+            format!("{:?}", m.upgradeable_read()),
+            expected("RwLockUpgradableGuard")
+        );
+        assert_eq!(format!("{:?}", m.write()), expected("RwLockWriteGuard"));
+    }
+
+    #[test]
+    fn test_pointer() {
+        let m = RwLock::new(0);
+        let addr = format!("{:p}", m.as_mut_ptr());
+        assert_eq!(format!("{:p}", m), addr);
+        assert_eq!(format!("{:p}", m.read()), addr);
+        assert_eq!(format!("{:p}", m.upgradeable_read()), addr);
+        assert_eq!(format!("{:p}", m.write()), addr);
+        assert_eq!(
+            format!("{:p}", super::RwLockReadGuard::map(m.read(), |x| x)),
+            addr
+        );
+        assert_eq!(
+            format!("{:p}", super::RwLockWriteGuard::map(m.write(), |x| x)),
+            addr
+        );
+    }
+
+    #[test]
+    fn test_try_with_upgraded() {
+        let m = RwLock::new(0);
+        {
+            let r = m.read();
+            let mut upg = m.upgradeable_read();
+            assert_eq!(upg.try_with_upgraded(|x| *x += 1), None);
+            drop(r);
+            assert_eq!(
+                upg.try_with_upgraded(|x| {
+                    *x += 1;
+                    *x
+                }),
+                Some(1)
+            );
+            assert!(m.try_read().is_none());
+            assert!(m.try_upgradeable_read().is_none());
+            assert_eq!(*upg, 1);
+        }
+        assert!(m.try_write().is_some());
+    }
 }
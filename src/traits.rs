@@ -0,0 +1,111 @@
+//! Traits implemented across the primitives in this crate, for code that is generic over them.
+
+/// A guard that provides shared access to locked data.
+///
+/// This is implemented by every guard in this crate, so generic code can accept a guard regardless
+/// of which lock it came from.
+///
+/// Note that, with this trait in scope, calling `get` with method syntax on a guard resolves to
+/// [`Guard::get`] rather than a method of the same name on the locked data.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(all(feature = "mutex", feature = "rwlock"))]
+/// # fn main() {
+/// use nospin::{Mutex, RwLock, traits::Guard};
+///
+/// fn port(config: impl Guard<Target = (&'static str, u16)>) -> u16 {
+///     config.get().1
+/// }
+///
+/// let mutex = Mutex::new(("localhost", 80));
+/// let rwlock = RwLock::new(("localhost", 443));
+///
+/// assert_eq!(port(mutex.lock()), 80);
+/// assert_eq!(port(rwlock.read()), 443);
+/// # }
+/// # #[cfg(not(all(feature = "mutex", feature = "rwlock")))]
+/// # fn main() {}
+/// ```
+pub trait Guard {
+    /// The type of the data the guard provides access to.
+    type Target: ?Sized;
+
+    /// Returns a shared reference to the locked data.
+    fn get(&self) -> &Self::Target;
+}
+
+/// A guard that additionally provides mutable access to locked data.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(all(feature = "mutex", feature = "rwlock"))]
+/// # fn main() {
+/// use nospin::{Mutex, RwLock, traits::GuardMut};
+///
+/// fn bump(mut counter: impl GuardMut<Target = u32>) {
+///     *counter.get_mut() += 1;
+/// }
+///
+/// let mutex = Mutex::new(0);
+/// let rwlock = RwLock::new(0);
+///
+/// bump(mutex.lock());
+/// bump(rwlock.write());
+/// assert_eq!(*mutex.lock() + *rwlock.read(), 2);
+/// # }
+/// # #[cfg(not(all(feature = "mutex", feature = "rwlock")))]
+/// # fn main() {}
+/// ```
+pub trait GuardMut: Guard {
+    /// Returns a mutable reference to the locked data.
+    fn get_mut(&mut self) -> &mut Self::Target;
+}
+
+/// A lock that can be acquired exclusively without panicking.
+///
+/// This is implemented by [`Mutex`](crate::Mutex), through [`Mutex::try_lock`](crate::Mutex::try_lock), and by
+/// [`RwLock`](crate::RwLock), through [`RwLock::try_write`](crate::RwLock::try_write), so that retry logic can be
+/// written once for either.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(all(feature = "mutex", feature = "rwlock"))]
+/// # fn main() {
+/// use nospin::{Mutex, RwLock, traits::{GuardMut, TryLock}};
+///
+/// fn try_bump(lock: &impl TryLock<Target = u32>, attempts: usize) -> bool {
+///     for _ in 0..attempts {
+///         if let Some(mut guard) = lock.try_lock() {
+///             *guard.get_mut() += 1;
+///             return true;
+///         }
+///     }
+///     false
+/// }
+///
+/// let mutex = Mutex::new(0);
+/// let rwlock = RwLock::new(0);
+///
+/// assert!(try_bump(&mutex, 3));
+/// let _held = rwlock.read();
+/// assert!(!try_bump(&rwlock, 3));
+/// # }
+/// # #[cfg(not(all(feature = "mutex", feature = "rwlock")))]
+/// # fn main() {}
+/// ```
+pub trait TryLock {
+    /// The type of the data protected by the lock.
+    type Target: ?Sized;
+
+    /// The guard returned when the lock is acquired.
+    type Guard<'a>: GuardMut<Target = Self::Target>
+    where
+        Self: 'a;
+
+    /// Attempts to acquire the lock exclusively, returning `None` if it is already held.
+    fn try_lock(&self) -> Option<Self::Guard<'_>>;
+}
@@ -0,0 +1,14 @@
+#![cfg(all(feature = "mutex", feature = "rwlock"))]
+
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile_fail/*.rs");
+    // With `strict_send_sync` the lock itself is not `Sync` either, which changes the errors reported
+    #[cfg(all(feature = "arc_lock", not(feature = "strict_send_sync")))]
+    t.compile_fail("tests/compile_fail/arc_lock/*.rs");
+    #[cfg(feature = "strict_send_sync")]
+    t.compile_fail("tests/compile_fail/strict_send_sync/*.rs");
+    #[cfg(feature = "branded")]
+    t.compile_fail("tests/compile_fail/branded/*.rs");
+}
@@ -0,0 +1,10 @@
+use std::{cell::Cell, sync::Arc};
+
+use nospin::Mutex;
+
+fn assert_sync<T: Sync>(_: &T) {}
+
+fn main() {
+    let guard = Arc::new(Mutex::new(Cell::new(0))).lock_owned();
+    assert_sync(&guard);
+}
@@ -0,0 +1,7 @@
+use nospin::Mutex;
+
+fn main() {
+    let lock = Mutex::new(0);
+    let guard = lock.scope(|guard| guard);
+    assert_eq!(*guard, 0);
+}
@@ -0,0 +1,11 @@
+use nospin::{Mutex, MutexGuard};
+
+fn main() {
+    let lock = Mutex::new(0);
+    let (data, token) = MutexGuard::leak_with_token(lock.lock());
+    token.unlock(data);
+
+    // The lock has been released, so the leaked reference must no longer be usable
+    let _guard = lock.lock();
+    *data = 1;
+}
@@ -0,0 +1,9 @@
+use nospin::{RwLock, RwLockWriteGuard};
+
+fn main() {
+    let lock = RwLock::new(0);
+    let guard = lock.write();
+    let data = RwLockWriteGuard::leak(guard);
+    *data = 1;
+    drop(guard);
+}
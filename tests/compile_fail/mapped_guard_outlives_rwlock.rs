@@ -0,0 +1,10 @@
+use nospin::{MappedRwLockReadGuard, RwLock, RwLockReadGuard};
+
+fn main() {
+    let mapped: MappedRwLockReadGuard<'_, i32>;
+    {
+        let lock = RwLock::new((1, 2));
+        mapped = RwLockReadGuard::map(lock.read(), |pair| &pair.0);
+    }
+    assert_eq!(*mapped, 1);
+}
@@ -0,0 +1,10 @@
+use nospin::{Mutex, MutexGuard};
+
+fn main() {
+    let guard: MutexGuard<'_, i32>;
+    {
+        let lock = Mutex::new(0);
+        guard = lock.lock();
+    }
+    assert_eq!(*guard, 0);
+}
@@ -0,0 +1,7 @@
+use nospin::Mutex;
+
+static COUNTER: Mutex<i32> = Mutex::new(0);
+
+fn main() {
+    *COUNTER.lock() += 1;
+}
@@ -0,0 +1,7 @@
+use nospin::RwLock;
+
+fn main() {
+    let lock = RwLock::new(0);
+    let upgradeable = lock.upgradeable_read();
+    *upgradeable = 1;
+}